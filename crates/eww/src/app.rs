@@ -4,9 +4,16 @@ use crate::{
     display_backend::DisplayBackend,
     error_handling_ctx,
     gtk::prelude::{ContainerExt, CssProviderExt, GtkWindowExt, StyleContextExt, WidgetExt},
+    logging,
+    opts::BatchOperation,
     paths::EwwPaths,
-    script_var_handler::ScriptVarHandlerHandle,
-    state::scope_graph::{ScopeGraph, ScopeIndex},
+    script_var_handler::{ScriptVarHandlerHandle, ScriptVarStats},
+    state::{
+        persistence,
+        scope_graph::{ScopeGraph, ScopeGraphEvent, ScopeIndex},
+    },
+    window_arguments::WindowArguments,
+    window_initiator::WindowInitiator,
     *,
 };
 use anyhow::anyhow;
@@ -15,7 +22,7 @@ use eww_shared_util::{Span, VarName};
 use glib::ObjectExt;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use simplexpr::dynval::DynVal;
+use simplexpr::{dynval::DynVal, SimplExpr};
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
@@ -23,15 +30,9 @@ use std::{
 };
 use tokio::sync::mpsc::UnboundedSender;
 use yuck::{
-    config::{
-        monitor::MonitorIdentifier,
-        script_var_definition::ScriptVarDefinition,
-        window_definition::WindowDefinition,
-        window_geometry::{AnchorPoint, WindowGeometry},
-    },
+    config::{monitor::MonitorIdentifier, script_var_definition::ScriptVarDefinition, window_geometry::WindowGeometry},
     error::DiagError,
     gen_diagnostic,
-    value::Coords,
 };
 
 /// A command for the eww daemon.
@@ -40,20 +41,18 @@ use yuck::{
 #[derive(Debug)]
 pub enum DaemonCommand {
     NoOp,
-    UpdateVars(Vec<(VarName, DynVal)>),
+    /// Update global variables. The sender is only given for updates requested via the CLI.
+    UpdateVars(Vec<(VarName, DynVal)>, Option<DaemonResponseSender>),
     ReloadConfigAndCss(DaemonResponseSender),
-    OpenInspector,
+    ReloadCss(DaemonResponseSender),
+    OpenInspector(DaemonResponseSender),
     OpenMany {
         windows: Vec<String>,
         should_toggle: bool,
         sender: DaemonResponseSender,
     },
     OpenWindow {
-        window_name: String,
-        pos: Option<Coords>,
-        size: Option<Coords>,
-        anchor: Option<AnchorPoint>,
-        screen: Option<MonitorIdentifier>,
+        window_args: WindowArguments,
         should_toggle: bool,
         sender: DaemonResponseSender,
     },
@@ -61,28 +60,89 @@ pub enum DaemonCommand {
         windows: Vec<String>,
         sender: DaemonResponseSender,
     },
-    KillServer,
-    CloseAll,
+    /// Stop the daemon. The sender is `None` if the daemon is stopped internally, i.e. after receiving a signal.
+    KillServer(Option<DaemonResponseSender>),
+    CloseAll(DaemonResponseSender),
     PrintState {
         all: bool,
+        json: bool,
         sender: DaemonResponseSender,
     },
     GetVar {
         name: String,
+        json: bool,
+        sender: DaemonResponseSender,
+    },
+    PrintDebug {
+        json: bool,
+        sender: DaemonResponseSender,
+    },
+    PrintGraph {
+        json: bool,
+        sender: DaemonResponseSender,
+    },
+    PrintStatus {
+        json: bool,
+        sender: DaemonResponseSender,
+    },
+    PrintWindows {
+        json: bool,
+        sender: DaemonResponseSender,
+    },
+    Batch {
+        operations: Vec<BatchOperation>,
+        sender: DaemonResponseSender,
+    },
+    /// Change parts of the json values of global variables.
+    /// The names are either paths into the value, like `settings.volume`, or, if `merge` is set,
+    /// just variable names, with the values being json merge patches.
+    PatchVars {
+        patches: Vec<(VarName, DynVal)>,
+        merge: bool,
+        sender: DaemonResponseSender,
+    },
+    ToggleVar {
+        name: VarName,
+        sender: DaemonResponseSender,
+    },
+    IncrVar {
+        name: VarName,
+        amount: f64,
+        sender: DaemonResponseSender,
+    },
+    /// Append a value to a json array. If the value isn't valid json, it is appended as a string.
+    PushVar {
+        name: VarName,
+        value: String,
+        max_length: Option<usize>,
+        sender: DaemonResponseSender,
+    },
+    PopVar {
+        name: VarName,
+        sender: DaemonResponseSender,
+    },
+    /// Stream every update of the given global variables to the sender, until it is closed.
+    Subscribe {
+        vars: Vec<VarName>,
+        json: bool,
         sender: DaemonResponseSender,
     },
-    PrintDebug(DaemonResponseSender),
-    PrintGraph(DaemonResponseSender),
-    PrintWindows(DaemonResponseSender),
 }
 
 /// An opened window.
 #[derive(Debug)]
 pub struct EwwWindow {
+    /// Name of the window definition
     pub name: String,
+    /// Id of this instance of the window
+    pub instance_id: String,
     pub scope_index: ScopeIndex,
     pub gtk_window: gtk::Window,
     pub destroy_event_handler_id: Option<glib::SignalHandlerId>,
+    /// The monitor the window was opened on, if one was specified
+    pub monitor: Option<MonitorIdentifier>,
+    /// The position and size the window was opened with, if the window has a geometry
+    pub geometry: Option<gdk::Rectangle>,
 }
 
 impl EwwWindow {
@@ -91,7 +151,7 @@ impl EwwWindow {
     /// You need to make sure that the scope get's properly cleaned from the state graph
     /// and that script-vars get cleaned up properly
     pub fn close(self) {
-        log::info!("Closing gtk window {}", self.name);
+        log::info!("Closing gtk window {}", self.instance_id);
         self.gtk_window.close();
         if let Some(handler_id) = self.destroy_event_handler_id {
             self.gtk_window.disconnect(handler_id);
@@ -103,11 +163,13 @@ pub struct App<B> {
     pub display_backend: B,
     pub scope_graph: Rc<RefCell<ScopeGraph>>,
     pub eww_config: config::EwwConfig,
-    /// Map of all currently open windows
+    /// Map of all currently open windows, by their instance id
     pub open_windows: HashMap<String, EwwWindow>,
-    /// Window names that are supposed to be open, but failed.
+    /// Instance ids of windows that are supposed to be open, but failed.
     /// When reloading the config, these should be opened again.
     pub failed_windows: HashSet<String>,
+    /// Arguments of all window instances that are open or failed to open, used to reopen them when reloading the config
+    pub instance_id_to_args: HashMap<String, WindowArguments>,
     pub css_provider: gtk::CssProvider,
 
     /// Sender to send [`DaemonCommand`]s
@@ -124,6 +186,7 @@ impl<B> std::fmt::Debug for App<B> {
             .field("eww_config", &self.eww_config)
             .field("open_windows", &self.open_windows)
             .field("failed_windows", &self.failed_windows)
+            .field("instance_id_to_args", &self.instance_id_to_args)
             .field("paths", &self.paths)
             .finish()
     }
@@ -136,14 +199,60 @@ impl<B: DisplayBackend> App<B> {
         let result: Result<_> = try {
             match event {
                 DaemonCommand::NoOp => {}
-                DaemonCommand::OpenInspector => {
+                DaemonCommand::OpenInspector(sender) => {
                     gtk::Window::set_interactive_debugging(true);
+                    sender.send_success(String::new())?;
                 }
-                DaemonCommand::UpdateVars(mappings) => {
-                    for (var_name, new_value) in mappings {
-                        self.update_global_variable(var_name, new_value);
+                DaemonCommand::UpdateVars(mappings, sender) => {
+                    let errors = self.update_global_variables(mappings);
+                    match sender {
+                        Some(sender) => sender.respond_with_error_list(errors)?,
+                        None => errors.into_iter().for_each(error_handling_ctx::print_error),
                     }
                 }
+                DaemonCommand::Batch { operations, sender } => {
+                    let errors = self.run_batch(operations);
+                    sender.respond_with_error_list(errors)?;
+                }
+                DaemonCommand::ToggleVar { name, sender } => {
+                    let result = self.modify_global_variable(name, |value| {
+                        let new_value = DynVal::from(!value.as_bool()?);
+                        Ok((new_value.clone(), new_value.into_inner()))
+                    });
+                    sender.respond_with_output(result)?;
+                }
+                DaemonCommand::IncrVar { name, amount, sender } => {
+                    let result = self.modify_global_variable(name, |value| {
+                        let new_value = DynVal::from(value.as_f64()? + amount);
+                        Ok((new_value.clone(), new_value.into_inner()))
+                    });
+                    sender.respond_with_output(result)?;
+                }
+                DaemonCommand::PushVar { name, value, max_length, sender } => {
+                    let result = self.modify_global_variable(name, |current| {
+                        let mut items = json_array_value(current)?;
+                        items.push(serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value)));
+                        if let Some(max_length) = max_length {
+                            let overflow = items.len().saturating_sub(max_length);
+                            items.drain(..overflow);
+                        }
+                        let length = items.len().to_string();
+                        Ok((DynVal::try_from(serde_json::Value::Array(items))?, length))
+                    });
+                    sender.respond_with_output(result)?;
+                }
+                DaemonCommand::PopVar { name, sender } => {
+                    let result = self.modify_global_variable(name, |current| {
+                        let mut items = json_array_value(current)?;
+                        let popped = items.pop().context("Can't pop from an empty array")?;
+                        Ok((DynVal::try_from(serde_json::Value::Array(items))?, DynVal::from(&popped).into_inner()))
+                    });
+                    sender.respond_with_output(result)?;
+                }
+                DaemonCommand::PatchVars { patches, merge, sender } => match self.apply_json_patches(patches, merge) {
+                    Ok(mappings) => sender.respond_with_error_list(self.update_global_variables(mappings))?,
+                    Err(err) => sender.respond_with_result::<()>(Err(err))?,
+                },
                 DaemonCommand::ReloadConfigAndCss(sender) => {
                     let mut errors = Vec::new();
 
@@ -151,28 +260,27 @@ impl<B: DisplayBackend> App<B> {
                     if let Err(e) = config_result.and_then(|new_config| self.load_config(new_config)) {
                         errors.push(e)
                     }
-                    match crate::config::scss::parse_scss_from_config(self.paths.get_config_dir()) {
-                        Ok((file_id, css)) => {
-                            if let Err(e) = self.load_css(file_id, &css) {
-                                errors.push(anyhow!(e));
-                            }
-                        }
-                        Err(e) => {
-                            errors.push(e);
-                        }
+                    if let Err(e) = self.reload_css() {
+                        errors.push(e);
                     }
 
                     sender.respond_with_error_list(errors)?;
                 }
-                DaemonCommand::KillServer => {
+                DaemonCommand::ReloadCss(sender) => {
+                    sender.respond_with_result(self.reload_css())?;
+                }
+                DaemonCommand::KillServer(sender) => {
                     log::info!("Received kill command, stopping server!");
                     self.stop_application();
+                    if let Some(sender) = sender {
+                        sender.send_success(String::new())?;
+                    }
                 }
-                DaemonCommand::CloseAll => {
+                DaemonCommand::CloseAll(sender) => {
                     log::info!("Received close command, closing all windows");
-                    for window_name in self.open_windows.keys().cloned().collect::<Vec<String>>() {
-                        self.close_window(&window_name)?;
-                    }
+                    let window_names = self.open_windows.keys().cloned().collect::<Vec<String>>();
+                    let errors = window_names.iter().map(|window_name| self.close_window(window_name)).filter_map(Result::err);
+                    sender.respond_with_error_list(errors)?;
                 }
                 DaemonCommand::OpenMany { windows, should_toggle, sender } => {
                     let errors = windows
@@ -181,64 +289,106 @@ impl<B: DisplayBackend> App<B> {
                             if should_toggle && self.open_windows.contains_key(w) {
                                 self.close_window(w)
                             } else {
-                                self.open_window(w, None, None, None, None)
+                                self.open_window(&WindowArguments::new(w.clone()))
                             }
                         })
                         .filter_map(Result::err);
                     sender.respond_with_error_list(errors)?;
                 }
-                DaemonCommand::OpenWindow { window_name, pos, size, anchor, screen: monitor, should_toggle, sender } => {
-                    let is_open = self.open_windows.contains_key(&window_name);
-                    let result = if !is_open {
-                        self.open_window(&window_name, pos, size, monitor, anchor)
-                    } else if should_toggle {
-                        self.close_window(&window_name)
-                    } else {
-                        Ok(())
-                    };
+                DaemonCommand::OpenWindow { window_args, should_toggle, sender } => {
+                    let result = self.open_or_toggle_window(window_args, should_toggle);
                     sender.respond_with_result(result)?;
                 }
                 DaemonCommand::CloseWindows { windows, sender } => {
                     let errors = windows.iter().map(|window| self.close_window(window)).filter_map(Result::err);
                     sender.respond_with_error_list(errors)?;
                 }
-                DaemonCommand::PrintState { all, sender } => {
+                DaemonCommand::PrintState { all, json, sender } => {
                     let scope_graph = self.scope_graph.borrow();
                     let used_globals_names = scope_graph.currently_used_globals();
-                    let output = scope_graph
-                        .global_scope()
-                        .data
-                        .iter()
-                        .filter(|(key, _)| all || used_globals_names.contains(*key))
-                        .map(|(key, value)| format!("{}: {}", key, value))
-                        .join("\n");
-                    sender.send_success(output)?
+                    let variables =
+                        scope_graph.global_scope().data.iter().filter(|(key, _)| all || used_globals_names.contains(*key));
+                    if json {
+                        let output =
+                            variables.map(|(key, value)| (key.to_string(), self.global_variable_json(key, value))).collect();
+                        sender.send_json(serde_json::Value::Object(output))?
+                    } else {
+                        let output = variables.map(|(key, value)| format!("{}: {}", key, value)).join("\n");
+                        sender.send_success(output)?
+                    }
                 }
-                DaemonCommand::GetVar { name, sender } => {
+                DaemonCommand::GetVar { name, json, sender } => {
                     let scope_graph = &*self.scope_graph.borrow();
                     let vars = &scope_graph.global_scope().data;
-                    match vars.get(name.as_str()) {
-                        Some(x) => sender.send_success(x.to_string())?,
+                    match vars.get_key_value(name.as_str()) {
+                        Some((key, value)) if json => sender.send_json(self.global_variable_json(key, value))?,
+                        Some((_, value)) => sender.send_success(value.to_string())?,
                         None => sender.send_failure(format!("Variable not found \"{}\"", name))?,
                     }
                 }
-                DaemonCommand::PrintWindows(sender) => {
-                    let output = self
-                        .eww_config
-                        .get_windows()
-                        .keys()
-                        .map(|window_name| {
-                            let is_open = self.open_windows.contains_key(window_name);
-                            format!("{}{}", if is_open { "*" } else { "" }, window_name)
-                        })
-                        .join("\n");
-                    sender.send_success(output)?
+                DaemonCommand::PrintWindows { json, sender } => {
+                    let window_names = self.eww_config.get_windows().keys();
+                    if json {
+                        let output = window_names.flat_map(|window_name| self.window_json(window_name)).collect();
+                        sender.send_json(serde_json::Value::Array(output))?
+                    } else {
+                        let output = window_names
+                            .flat_map(|window_name| {
+                                let instance_ids = self.instance_ids_of(window_name);
+                                if instance_ids.is_empty() {
+                                    return vec![window_name.to_string()];
+                                }
+                                instance_ids
+                                    .into_iter()
+                                    .map(|id| {
+                                        let is_open = self.open_windows.contains_key(id);
+                                        let name = if id == window_name { String::new() } else { format!(" ({})", window_name) };
+                                        format!("{}{}{}", if is_open { "*" } else { "" }, id, name)
+                                    })
+                                    .collect()
+                            })
+                            .join("\n");
+                        sender.send_success(output)?
+                    }
                 }
-                DaemonCommand::PrintDebug(sender) => {
-                    let output = format!("{:#?}", &self);
-                    sender.send_success(output)?
+                DaemonCommand::PrintDebug { json, sender } => {
+                    if json {
+                        sender.send_json(self.debug_json())?
+                    } else {
+                        let output = format!("{:#?}", &self);
+                        sender.send_success(output)?
+                    }
+                }
+                DaemonCommand::PrintStatus { json, sender } => {
+                    if json {
+                        sender.send_json(self.status_json())?
+                    } else {
+                        sender.send_success(self.status_text())?
+                    }
+                }
+                DaemonCommand::PrintGraph { json, sender } => {
+                    if json {
+                        sender.send_json(self.scope_graph.borrow().visualize_json())?
+                    } else {
+                        sender.send_success(self.scope_graph.borrow().visualize())?
+                    }
+                }
+                DaemonCommand::Subscribe { vars, json, sender } => {
+                    let mut scope_graph = self.scope_graph.borrow_mut();
+                    match vars.iter().find(|name| !scope_graph.global_scope().data.contains_key(*name)) {
+                        Some(name) => sender.send_failure(format!("Variable not found \"{}\"", name))?,
+                        None => {
+                            // Clean up the subscription as soon as the client disconnects, even if its variables never change
+                            let closed = sender.closed();
+                            let event_sender = scope_graph.event_sender.clone();
+                            glib::MainContext::default().spawn_local(async move {
+                                closed.await;
+                                let _ = event_sender.send(ScopeGraphEvent::RemoveClosedSubscriptions);
+                            });
+                            scope_graph.subscribe_to_globals(vars, json, sender);
+                        }
+                    }
                 }
-                DaemonCommand::PrintGraph(sender) => sender.send_success(self.scope_graph.borrow().visualize())?,
             }
         };
 
@@ -247,6 +397,157 @@ impl<B: DisplayBackend> App<B> {
         }
     }
 
+    /// Json representation of a global variable, including the state of the script-var providing it, if there is one.
+    fn global_variable_json(&self, name: &VarName, value: &DynVal) -> serde_json::Value {
+        match self.eww_config.get_script_var(name) {
+            Ok(script_var) => serde_json::json!({
+                "value": value.as_str(),
+                "type": match script_var {
+                    ScriptVarDefinition::Poll(_) => "poll",
+                    ScriptVarDefinition::Listen(_) => "listen",
+                },
+                "running": self.script_var_handler.stats_of(name).is_running(),
+            }),
+            Err(_) => serde_json::json!({ "value": value.as_str(), "type": "var" }),
+        }
+    }
+
+    /// Instance ids of all instances of the given window that are open or failed to open, sorted by id.
+    fn instance_ids_of(&self, window_name: &str) -> Vec<&str> {
+        self.instance_id_to_args
+            .values()
+            .filter(|args| args.window_name == window_name)
+            .map(|args| args.instance_id.as_str())
+            .sorted()
+            .collect()
+    }
+
+    /// Json representation of a configured window and its current state,
+    /// with one entry per instance of the window that is open or failed to open.
+    fn window_json(&self, window_name: &str) -> Vec<serde_json::Value> {
+        let instance_ids = self.instance_ids_of(window_name);
+        if instance_ids.is_empty() {
+            return vec![serde_json::json!({ "name": window_name, "id": window_name, "open": false, "failed": false })];
+        }
+        instance_ids
+            .into_iter()
+            .map(|id| match self.open_windows.get(id) {
+                Some(window) => {
+                    let geometry = window.geometry.map(
+                        |rect| serde_json::json!({ "x": rect.x(), "y": rect.y(), "width": rect.width(), "height": rect.height() }),
+                    );
+                    serde_json::json!({
+                        "name": window_name,
+                        "id": id,
+                        "open": true,
+                        "failed": false,
+                        "monitor": window.monitor.as_ref().map(|monitor| monitor.to_string()),
+                        "geometry": geometry,
+                    })
+                }
+                None => serde_json::json!({
+                    "name": window_name,
+                    "id": id,
+                    "open": false,
+                    "failed": self.failed_windows.contains(id),
+                }),
+            })
+            .collect()
+    }
+
+    fn debug_json(&self) -> serde_json::Value {
+        let script_vars = self
+            .scope_graph
+            .borrow()
+            .global_scope()
+            .data
+            .iter()
+            .filter(|(name, _)| self.eww_config.get_script_var(name).is_ok())
+            .map(|(name, value)| (name.to_string(), self.global_variable_json(name, value)))
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::json!({
+            "paths": {
+                "config_dir": self.paths.get_config_dir().display().to_string(),
+                "ipc_socket_file": self.paths.get_ipc_socket_file().display().to_string(),
+                "log_file": self.paths.get_log_file().display().to_string(),
+                "state_file": self.paths.get_state_file().display().to_string(),
+            },
+            "windows": self.eww_config.get_windows().keys().flat_map(|name| self.window_json(name)).collect::<Vec<_>>(),
+            "script_vars": script_vars,
+        })
+    }
+
+    /// All script-vars of the configuration sorted by name, with their type, whether they are running and their statistics.
+    fn script_var_statuses(&self) -> Vec<(&VarName, &'static str, bool, ScriptVarStats)> {
+        let mut stats = self.script_var_handler.stats();
+        self.eww_config
+            .get_script_vars()
+            .iter()
+            .sorted_by(|(a, _), (b, _)| a.0.cmp(&b.0))
+            .map(|(name, definition)| {
+                let kind = match definition {
+                    ScriptVarDefinition::Poll(_) => "poll",
+                    ScriptVarDefinition::Listen(_) => "listen",
+                };
+                let stats = stats.remove(name).unwrap_or_default();
+                (name, kind, stats.is_running(), stats)
+            })
+            .collect()
+    }
+
+    fn status_json(&self) -> serde_json::Value {
+        let unix_secs = |time: std::time::SystemTime| time.duration_since(std::time::UNIX_EPOCH).ok().map(|x| x.as_secs());
+        let script_vars = self
+            .script_var_statuses()
+            .into_iter()
+            .map(|(name, kind, running, stats)| {
+                serde_json::json!({
+                    "name": name.to_string(),
+                    "type": kind,
+                    "running": running,
+                    "pid": stats.pid,
+                    "last_update": stats.last_update.and_then(unix_secs),
+                    "last_error": stats.last_error,
+                    "run_count": stats.run_count,
+                    "average_poll_duration_ms": stats.average_poll_duration().map(|x| x.as_secs_f64() * 1000.0),
+                })
+            })
+            .collect::<Vec<_>>();
+        let scope_graph = self.scope_graph.borrow();
+        serde_json::json!({
+            "windows": { "open": self.open_windows.len(), "failed": self.failed_windows.len() },
+            "scope_graph": { "scopes": scope_graph.scope_count(), "listeners": scope_graph.listener_count() },
+            "script_vars": script_vars,
+        })
+    }
+
+    fn status_text(&self) -> String {
+        let scope_graph = self.scope_graph.borrow();
+        let mut lines = vec![
+            format!("windows: {} open, {} failed", self.open_windows.len(), self.failed_windows.len()),
+            format!("scope graph: {} scopes, {} listeners", scope_graph.scope_count(), scope_graph.listener_count()),
+            "script vars:".to_string(),
+        ];
+        for (name, kind, running, stats) in self.script_var_statuses() {
+            let mut line = format!("  {} ({}, {})", name, kind, if running { "running" } else { "stopped" });
+            if let Some(pid) = stats.pid {
+                line.push_str(&format!(", pid {}", pid));
+            }
+            line.push_str(&format!(", {} {}", stats.run_count, if kind == "poll" { "runs" } else { "updates" }));
+            if let Some(duration) = stats.average_poll_duration() {
+                line.push_str(&format!(", {}ms on average", duration.as_millis()));
+            }
+            if let Some(elapsed) = stats.last_update.and_then(|time| time.elapsed().ok()) {
+                line.push_str(&format!(", last update {} ago", util::format_duration_short(elapsed.as_secs())));
+            }
+            lines.push(line);
+            if let Some(error) = stats.last_error {
+                lines.push(format!("    last error: {}", error));
+            }
+        }
+        lines.join("\n")
+    }
+
     /// Fully stop eww:
     /// close all windows, stop the script_var_handler, quit the gtk appliaction and send the exit instruction to the lifecycle manager
     fn stop_application(&mut self) {
@@ -258,13 +559,124 @@ impl<B: DisplayBackend> App<B> {
         let _ = crate::application_lifecycle::send_exit();
     }
 
-    fn update_global_variable(&mut self, name: VarName, value: DynVal) {
-        let result = self.scope_graph.borrow_mut().update_global_value(&name, value);
-        if let Err(err) = result {
+    /// Update the given global variables. The widgets are only updated once all of the variables have been changed.
+    /// Returns the errors that occurred while updating the variables.
+    fn update_global_variables(&mut self, mappings: Vec<(VarName, DynVal)>) -> Vec<anyhow::Error> {
+        let names = mappings.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        let errors: Vec<_> = self.scope_graph.borrow_mut().with_deferred_listeners(|scope_graph| {
+            mappings.into_iter().filter_map(|(name, value)| scope_graph.update_global_value(&name, value).err()).collect()
+        });
+
+        for name in &names {
+            self.apply_run_while_expressions_mentioning(name);
+        }
+        if names.iter().any(|name| self.eww_config.get_persisted_vars().contains(name)) {
+            self.save_persisted_vars();
+        }
+        errors
+    }
+
+    /// Write the current values of all persisted variables to the state file.
+    fn save_persisted_vars(&self) {
+        let scope_graph = self.scope_graph.borrow();
+        let persisted_vars = self.eww_config.get_persisted_vars();
+        let values = scope_graph.global_scope().data.iter().filter(|(name, _)| persisted_vars.contains(*name));
+        if let Err(err) = persistence::save_persisted_vars(self.paths.get_state_file(), values) {
             error_handling_ctx::print_error(err);
         }
+    }
 
-        self.apply_run_while_expressions_mentioning(&name);
+    /// Change the value of a global variable based on its current value.
+    /// `f` returns the new value, as well as the output that is sent back to the client.
+    fn modify_global_variable(&mut self, name: VarName, f: impl FnOnce(&DynVal) -> Result<(DynVal, String)>) -> Result<String> {
+        let current = self.scope_graph.borrow().global_scope().data.get(&name).cloned();
+        let current = current.with_context(|| format!("Variable not found \"{}\"", name))?;
+        let (new_value, output) = f(&current).with_context(|| format!("Failed to modify the value of {}", name))?;
+        match self.update_global_variables(vec![(name, new_value)]).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(output),
+        }
+    }
+
+    /// Compute the new values of the global variables targeted by the given patches, without updating them yet.
+    /// Fails if any of the variables doesn't contain valid json, or any of the patches can't be applied.
+    fn apply_json_patches(&self, patches: Vec<(VarName, DynVal)>, merge: bool) -> Result<Vec<(VarName, DynVal)>> {
+        let scope_graph = self.scope_graph.borrow();
+        let globals = &scope_graph.global_scope().data;
+        let mut new_values: Vec<(VarName, serde_json::Value)> = Vec::new();
+        for (target, patch) in patches {
+            let (name, path) = match target.0.split_once('.') {
+                Some((name, path)) if !merge => (VarName::from(name), path.split('.').collect::<Vec<_>>()),
+                _ => (target.clone(), Vec::new()),
+            };
+            let index = match new_values.iter().position(|(x, _)| *x == name) {
+                Some(index) => index,
+                None => {
+                    let current = globals.get(&name).with_context(|| format!("Variable not found \"{}\"", name))?;
+                    let current = current.as_json_value().with_context(|| format!("The value of {} is not valid json", name))?;
+                    new_values.push((name.clone(), current));
+                    new_values.len() - 1
+                }
+            };
+            let current = &mut new_values[index].1;
+            if merge {
+                let patch = patch.as_json_value().with_context(|| format!("Invalid json merge patch for {}", name))?;
+                util::json_merge_patch(current, patch);
+            } else {
+                let value = patch.as_json_value().unwrap_or(serde_json::Value::String(patch.into_inner()));
+                util::json_set_path(current, &path, value).with_context(|| format!("Failed to patch {}", target))?;
+            }
+        }
+        new_values.into_iter().map(|(name, value)| Ok((name, DynVal::try_from(value)?))).collect()
+    }
+
+    /// Run the operations of a batch: first all variable updates at once, then the window operations in order.
+    /// Returns the errors of all failed operations.
+    fn run_batch(&mut self, operations: Vec<BatchOperation>) -> Vec<anyhow::Error> {
+        let mut mappings = Vec::new();
+        let mut window_operations = Vec::new();
+        for operation in operations {
+            match operation {
+                BatchOperation::UpdateVars(x) => mappings.extend(x),
+                other => window_operations.push(other),
+            }
+        }
+
+        let mut errors = self.update_global_variables(mappings);
+        for operation in window_operations {
+            match operation {
+                BatchOperation::UpdateVars(_) => {}
+                BatchOperation::OpenWindow { window_name, id, args, screen, pos, size, anchor, should_toggle } => {
+                    let window_args = WindowArguments {
+                        pos,
+                        size,
+                        anchor,
+                        monitor: screen,
+                        args: args.into_iter().collect(),
+                        ..WindowArguments::new(window_name).with_instance_id(id)
+                    };
+                    errors.extend(self.open_or_toggle_window(window_args, should_toggle).err());
+                }
+                BatchOperation::OpenMany { windows, should_toggle } => {
+                    let results = windows.iter().map(|w| {
+                        if should_toggle && self.open_windows.contains_key(w) {
+                            self.close_window(w)
+                        } else {
+                            self.open_window(&WindowArguments::new(w.clone()))
+                        }
+                    });
+                    errors.extend(results.filter_map(Result::err));
+                }
+                BatchOperation::CloseWindows { windows } => {
+                    errors.extend(windows.iter().map(|window| self.close_window(window)).filter_map(Result::err));
+                }
+                BatchOperation::CloseAll => {
+                    let open_windows = self.open_windows.keys().cloned().collect::<Vec<_>>();
+                    errors.extend(open_windows.iter().map(|window| self.close_window(window)).filter_map(Result::err));
+                }
+            }
+        }
+        errors
     }
 
     /// Variables may be referenced in defpoll :run-while expressions.
@@ -275,29 +687,50 @@ impl<B: DisplayBackend> App<B> {
             Some(x) => x,
             None => return,
         };
-        let mentioning_vars = mentioning_vars.iter().filter_map(|name| self.eww_config.get_script_var(name).ok());
+        let mentioning_vars: Vec<_> =
+            mentioning_vars.iter().filter_map(|name| self.eww_config.get_script_var(name).ok()).cloned().collect();
         for var in mentioning_vars {
-            if let ScriptVarDefinition::Poll(poll_var) = var {
+            self.apply_run_while_expression(var);
+        }
+    }
+
+    /// Start the given script var if its run-while expression is true, and stop it otherwise.
+    /// Listen vars don't have a run-while expression, and are always started.
+    fn apply_run_while_expression(&mut self, var: ScriptVarDefinition) {
+        let run_while_result = match &var {
+            ScriptVarDefinition::Poll(poll_var) => {
                 let scope_graph = self.scope_graph.borrow();
-                let run_while_result = scope_graph
-                    .evaluate_simplexpr_in_scope(scope_graph.root_index, &poll_var.run_while_expr)
-                    .map(|v| v.as_bool());
-                match run_while_result {
-                    Ok(Ok(true)) => self.script_var_handler.add(var.clone()),
-                    Ok(Ok(false)) => self.script_var_handler.stop_for_variable(poll_var.name.clone()),
-                    Ok(Err(err)) => error_handling_ctx::print_error(anyhow!(err)),
-                    Err(err) => error_handling_ctx::print_error(anyhow!(err)),
-                };
+                scope_graph.evaluate_simplexpr_in_scope(scope_graph.root_index, &poll_var.run_while_expr).map(|v| v.as_bool())
             }
+            ScriptVarDefinition::Listen(_) => Ok(Ok(true)),
+        };
+        match run_while_result {
+            Ok(Ok(true)) => self.script_var_handler.add(var),
+            Ok(Ok(false)) => self.script_var_handler.stop_for_variable(var.name().clone()),
+            Ok(Err(err)) => error_handling_ctx::print_error(anyhow!(err)),
+            Err(err) => error_handling_ctx::print_error(anyhow!(err)),
+        };
+    }
+
+    /// Open a window instance, or close it instead if it is already open and `should_toggle` is set.
+    fn open_or_toggle_window(&mut self, window_args: WindowArguments, should_toggle: bool) -> Result<()> {
+        if !self.open_windows.contains_key(&window_args.instance_id) {
+            self.open_window(&window_args)
+        } else if should_toggle {
+            self.close_window(&window_args.instance_id)
+        } else {
+            Ok(())
         }
     }
 
-    /// Close a window and do all the required cleanups in the scope_graph and script_var_handler
-    fn close_window(&mut self, window_name: &str) -> Result<()> {
+    /// Close a window instance and do all the required cleanups in the scope_graph and script_var_handler
+    fn close_window(&mut self, instance_id: &str) -> Result<()> {
+        let _log_context = logging::window_context(instance_id);
         let eww_window = self
             .open_windows
-            .remove(window_name)
-            .with_context(|| format!("Tried to close window named '{}', but no such window was open", window_name))?;
+            .remove(instance_id)
+            .with_context(|| format!("Tried to close window with id '{}', but no such window was open", instance_id))?;
+        self.instance_id_to_args.remove(instance_id);
 
         let scope_index = eww_window.scope_index;
         eww_window.close();
@@ -313,34 +746,36 @@ impl<B: DisplayBackend> App<B> {
         Ok(())
     }
 
-    fn open_window(
-        &mut self,
-        window_name: &str,
-        pos: Option<Coords>,
-        size: Option<Coords>,
-        monitor: Option<MonitorIdentifier>,
-        anchor: Option<AnchorPoint>,
-    ) -> Result<()> {
-        self.failed_windows.remove(window_name);
-        log::info!("Opening window {}", window_name);
-
-        // if an instance of this is already running, close it
-        if self.open_windows.contains_key(window_name) {
-            self.close_window(window_name)?;
+    fn open_window(&mut self, window_args: &WindowArguments) -> Result<()> {
+        let instance_id = window_args.instance_id.as_str();
+        let window_name = window_args.window_name.as_str();
+        let _log_context = logging::window_context(instance_id);
+        self.failed_windows.remove(instance_id);
+        log::info!("Opening window {} as {}", window_name, instance_id);
+
+        // if an instance with this id is already running, close it
+        if self.open_windows.contains_key(instance_id) {
+            self.close_window(instance_id)?;
         }
+        self.instance_id_to_args.insert(instance_id.to_string(), window_args.clone());
 
         let open_result: Result<_> = try {
-            let mut window_def = self.eww_config.get_window(window_name)?.clone();
+            let window_def = self.eww_config.get_window(window_name)?.clone();
             assert_eq!(window_def.name, window_name, "window definition name did not equal the called window");
-            window_def.geometry = window_def.geometry.map(|x| x.override_if_given(anchor, pos, size));
+            let window_init = WindowInitiator::new(&window_def, window_args)?;
 
             let root_index = self.scope_graph.borrow().root_index;
 
+            // the arguments of the window instance are provided as attributes to the window scope
             let window_scope = self.scope_graph.borrow_mut().register_new_scope(
-                window_name.to_string(),
+                instance_id.to_string(),
                 Some(root_index),
                 root_index,
-                HashMap::new(),
+                window_init
+                    .local_variables
+                    .iter()
+                    .map(|(name, value)| (name.clone().into(), SimplExpr::Literal(value.clone())))
+                    .collect(),
             )?;
 
             let root_widget = crate::widgets::build_widget::build_gtk_widget(
@@ -351,9 +786,9 @@ impl<B: DisplayBackend> App<B> {
                 None,
             )?;
 
-            let monitor_geometry = get_monitor_geometry(monitor.or_else(|| window_def.monitor.clone()))?;
+            let monitor_geometry = get_monitor_geometry(window_init.monitor.clone())?;
 
-            let mut eww_window = initialize_window::<B>(monitor_geometry, root_widget, window_def, window_scope)?;
+            let mut eww_window = initialize_window::<B>(instance_id, &window_init, monitor_geometry, root_widget, window_scope)?;
             eww_window.gtk_window.style_context().add_class(window_name);
 
             // initialize script var handlers for variables. As starting a scriptvar with the script_var_handler is idempodent,
@@ -367,49 +802,109 @@ impl<B: DisplayBackend> App<B> {
 
             eww_window.destroy_event_handler_id = Some(eww_window.gtk_window.connect_destroy({
                 let app_evt_sender = self.app_evt_send.clone();
-                let window_name: String = eww_window.name.to_string();
+                let instance_id: String = eww_window.instance_id.to_string();
                 move |_| {
                     // we don't care about the actual error response from the daemon as this is mostly just a fallback.
                     // Generally, this should get disconnected before the gtk window gets destroyed.
                     // It serves as a fallback for when the window is closed manually.
                     let (response_sender, _) = daemon_response::create_pair();
-                    let command = DaemonCommand::CloseWindows { windows: vec![window_name.clone()], sender: response_sender };
+                    let command = DaemonCommand::CloseWindows { windows: vec![instance_id.clone()], sender: response_sender };
                     if let Err(err) = app_evt_sender.send(command) {
                         log::error!("Error sending close window command to daemon after gtk window destroy event: {}", err);
                     }
                 }
             }));
 
-            self.open_windows.insert(window_name.to_string(), eww_window);
+            self.open_windows.insert(instance_id.to_string(), eww_window);
         };
 
         if let Err(err) = open_result {
-            self.failed_windows.insert(window_name.to_string());
-            Err(err).with_context(|| format!("failed to open window `{}`", window_name))
+            self.failed_windows.insert(instance_id.to_string());
+            Err(err).with_context(|| format!("failed to open window `{}`", instance_id))
         } else {
             Ok(())
         }
     }
 
-    /// Load the given configuration, reloading all script-vars and attempting to reopen all windows that where opened.
+    /// Load the given configuration, reopening all windows whose definition changed.
+    /// Windows that are defined the same way as before stay open as they are.
+    /// Global variables whose definition did not change keep their current value,
+    /// and only script-vars whose definition changed or that got removed are restarted.
     pub fn load_config(&mut self, config: config::EwwConfig) -> Result<()> {
         log::info!("Reloading windows");
+        log::trace!("loading config: {:#?}", config);
 
-        self.script_var_handler.stop_all();
-        let old_handler = std::mem::replace(&mut self.script_var_handler, script_var_handler::init(self.app_evt_send.clone()));
-        old_handler.join_thread();
+        let old_config = std::mem::replace(&mut self.eww_config, config);
+        let changed_script_vars = self.eww_config.get_changed_script_vars(&old_config);
 
-        log::trace!("loading config: {:#?}", config);
+        for (name, old_definition) in old_config.get_script_vars() {
+            let unchanged = self.eww_config.get_script_var(name).map_or(false, |x| x.is_same_definition(old_definition));
+            if !unchanged {
+                log::debug!("stopping script-var {}, as its definition changed", name);
+                self.script_var_handler.stop_for_variable(name.clone());
+            }
+        }
+
+        let mut initial_state = self.eww_config.generate_initial_state()?;
+        let persisted_vars = self.eww_config.get_persisted_vars();
+        if let Err(err) = persistence::restore_persisted_vars(self.paths.get_state_file(), persisted_vars, &mut initial_state) {
+            error_handling_ctx::print_error(err);
+        }
+        for (name, current_value) in &self.scope_graph.borrow().global_scope().data {
+            if initial_state.contains_key(name) && self.eww_config.has_same_definition_of(&old_config, name) {
+                initial_state.insert(name.clone(), current_value.clone());
+            }
+        }
 
-        self.eww_config = config;
-        self.scope_graph.borrow_mut().clear(self.eww_config.generate_initial_state()?);
+        // Only windows whose definition, or the definition of any widget they use, changed get rebuilt.
+        // Windows that previously failed to open are always retried.
+        let changed_instances: Vec<String> = self
+            .open_windows
+            .iter()
+            .filter(|(_, window)| !self.eww_config.has_same_window_definition(&old_config, &window.name))
+            .map(|(instance_id, _)| instance_id.clone())
+            .collect();
+        let instances: Vec<WindowArguments> = changed_instances
+            .iter()
+            .chain(self.failed_windows.iter())
+            .filter_map(|instance_id| self.instance_id_to_args.get(instance_id))
+            .cloned()
+            .collect();
 
-        let window_names: Vec<String> =
-            self.open_windows.keys().cloned().chain(self.failed_windows.iter().cloned()).dedup().collect();
-        for window_name in &window_names {
-            self.open_window(window_name, None, None, None, None)?;
+        // The windows are closed directly rather than through `close_window`,
+        // to keep the script-vars they use running until the windows are reopened.
+        for instance_id in &changed_instances {
+            if let Some(window) = self.open_windows.remove(instance_id) {
+                let scope_index = window.scope_index;
+                window.close();
+                self.scope_graph.borrow_mut().remove_scope(scope_index);
+            }
         }
-        Ok(())
+        if let Err(err) = self.scope_graph.borrow_mut().replace_globals(initial_state) {
+            error_handling_ctx::print_error(err);
+        }
+
+        let results: Vec<Result<()>> = instances.iter().map(|window_args| self.open_window(window_args)).collect();
+
+        // Windows that stayed open don't start the script vars they use again, so changed script vars need to be restarted here.
+        let used_globals = self.scope_graph.borrow().currently_used_globals();
+        for name in changed_script_vars.iter().filter(|name| used_globals.contains(*name)) {
+            if let Ok(var) = self.eww_config.get_script_var(name) {
+                self.apply_run_while_expression(var.clone());
+            }
+        }
+
+        let unused_variables = self.scope_graph.borrow().currently_unused_globals();
+        for unused_var in unused_variables {
+            self.script_var_handler.stop_for_variable(unused_var);
+        }
+        results.into_iter().collect()
+    }
+
+    /// Read the stylesheet from the config directory and load it, leaving windows and variables untouched.
+    fn reload_css(&mut self) -> Result<()> {
+        let (file_id, css) = crate::config::scss::parse_scss_from_config(self.paths.get_config_dir())?;
+        self.load_css(file_id, &css)
     }
 
     /// Load a given CSS string into the gtk css provider, returning a nicely formatted [`DiagError`] when GTK errors out
@@ -435,21 +930,31 @@ impl<B: DisplayBackend> App<B> {
     }
 }
 
+/// Read the value of a variable as a json array. An empty value is treated as an empty array.
+fn json_array_value(value: &DynVal) -> Result<Vec<serde_json::Value>> {
+    if value.as_str().trim().is_empty() {
+        Ok(Vec::new())
+    } else {
+        Ok(value.as_json_array()?)
+    }
+}
+
 fn initialize_window<B: DisplayBackend>(
+    instance_id: &str,
+    window_init: &WindowInitiator,
     monitor_geometry: gdk::Rectangle,
     root_widget: gtk::Widget,
-    window_def: WindowDefinition,
     window_scope: ScopeIndex,
 ) -> Result<EwwWindow> {
-    let window = B::initialize_window(&window_def, monitor_geometry)
-        .with_context(|| format!("monitor {} is unavailable", window_def.monitor.clone().unwrap()))?;
+    let window = B::initialize_window(window_init, monitor_geometry)
+        .with_context(|| format!("monitor {} is unavailable", window_init.monitor.clone().unwrap()))?;
 
-    window.set_title(&format!("Eww - {}", window_def.name));
+    window.set_title(&format!("Eww - {}", window_init.name));
     window.set_position(gtk::WindowPosition::None);
     window.set_gravity(gdk::Gravity::Center);
 
-    if let Some(geometry) = window_def.geometry {
-        let actual_window_rect = get_window_rectangle(geometry, monitor_geometry);
+    let actual_window_rect = window_init.geometry.map(|geometry| get_window_rectangle(geometry, monitor_geometry));
+    if let Some(actual_window_rect) = actual_window_rect {
         window.set_size_request(actual_window_rect.width(), actual_window_rect.height());
         window.set_default_size(actual_window_rect.width(), actual_window_rect.height());
     }
@@ -467,21 +972,29 @@ fn initialize_window<B: DisplayBackend>(
 
     #[cfg(feature = "x11")]
     {
-        if let Some(geometry) = window_def.geometry {
+        if let Some(geometry) = window_init.geometry {
             let _ = apply_window_position(geometry, monitor_geometry, &window);
-            if window_def.backend_options.x11.window_type != yuck::config::backend_window_options::X11WindowType::Normal {
+            if window_init.backend_options.x11.window_type != yuck::config::backend_window_options::X11WindowType::Normal {
                 window.connect_configure_event(move |window, _| {
                     let _ = apply_window_position(geometry, monitor_geometry, window);
                     false
                 });
             }
         }
-        display_backend::set_xprops(&window, monitor_geometry, &window_def)?;
+        display_backend::set_xprops(&window, monitor_geometry, window_init)?;
     }
 
     window.show_all();
 
-    Ok(EwwWindow { name: window_def.name, gtk_window: window, scope_index: window_scope, destroy_event_handler_id: None })
+    Ok(EwwWindow {
+        name: window_init.name.clone(),
+        instance_id: instance_id.to_string(),
+        gtk_window: window,
+        scope_index: window_scope,
+        destroy_event_handler_id: None,
+        monitor: window_init.monitor.clone(),
+        geometry: actual_window_rect,
+    })
 }
 
 /// Apply the provided window-positioning rules to the window.
@@ -492,7 +1005,7 @@ fn apply_window_position(
     window: &gtk::Window,
 ) -> Result<()> {
     let gdk_window = window.window().context("Failed to get gdk window from gtk window")?;
-    window_geometry.size = Coords::from_pixels(window.size());
+    window_geometry.size = yuck::value::Coords::from_pixels(window.size());
     let actual_window_rect = get_window_rectangle(window_geometry, monitor_geometry);
 
     let gdk_origin = gdk_window.origin();
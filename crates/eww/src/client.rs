@@ -1,49 +1,109 @@
-use std::process::Stdio;
-
 use crate::{
+    completions, config, daemon_registry,
     daemon_response::DaemonResponse,
+    error_handling_ctx,
+    ipc_protocol::{self, Encoding, Handshake},
+    logging,
     opts::{self, ActionClientOnly},
     paths::EwwPaths,
+    util,
 };
-use anyhow::{Context, Result};
-use std::{
-    io::{Read, Write},
-    os::unix::net::UnixStream,
-};
+use anyhow::{bail, Context, Result};
+use std::{io::Write, os::unix::net::UnixStream, time::Duration};
 
 pub fn handle_client_only_action(paths: &EwwPaths, action: ActionClientOnly) -> Result<()> {
     match action {
-        ActionClientOnly::Logs => {
-            std::process::Command::new("tail")
-                .args(["-f", paths.get_log_file().to_string_lossy().as_ref()].iter())
-                .stdin(Stdio::null())
-                .spawn()?
-                .wait()?;
+        ActionClientOnly::Logs { level, since, grep, var } => {
+            let filter = logging::LogFilter::new(level.as_deref(), since, grep.as_deref(), var)?;
+            logging::follow_log_file(paths.get_log_file(), &filter)?;
+        }
+        ActionClientOnly::ListDaemons { json } => {
+            let daemons = daemon_registry::list_running()?;
+            if json {
+                println!("{}", serde_json::to_string(&daemons)?);
+            } else if daemons.is_empty() {
+                println!("No eww daemons running");
+            } else {
+                for daemon in daemons {
+                    println!(
+                        "{:<16} pid {:<8} up {:<8} {}",
+                        daemon.instance.as_deref().unwrap_or("(unnamed)"),
+                        daemon.pid,
+                        util::format_duration_short(daemon.uptime_secs()),
+                        daemon.config_dir.display()
+                    );
+                }
+            }
+        }
+        ActionClientOnly::Check => {
+            let errors = config::check::check_config(paths);
+            if !errors.is_empty() {
+                let error_count = errors.len();
+                errors.into_iter().for_each(error_handling_ctx::print_error);
+                bail!("Found {} problem(s) in the configuration", error_count);
+            }
+            println!("No problems found in {}", paths.get_config_dir().display());
         }
+        ActionClientOnly::ShellCompletions { shell } => completions::print_completion_script(shell),
+        ActionClientOnly::CompleteNames { kind } => completions::print_names(paths, kind)?,
     }
     Ok(())
 }
 
-/// Connect to the daemon and send the given request.
-/// Returns the response from the daemon, or None if the daemon did not provide any useful response. An Ok(None) response does _not_ indicate failure.
-pub fn do_server_call(stream: &mut UnixStream, action: &opts::ActionWithServer) -> Result<Option<DaemonResponse>> {
+/// Connect to the daemon and send the given request, waiting for the daemon to finish the command.
+/// Returns the response from the daemon, or None if the command doesn't produce a response. An Ok(None) response does _not_ indicate failure.
+/// If the daemon doesn't finish the command within the given timeout, it responds with a failure.
+pub fn do_server_call(
+    stream: &mut UnixStream,
+    action: &opts::ActionWithServer,
+    timeout: Duration,
+) -> Result<Option<DaemonResponse>> {
     log::debug!("Forwarding options to server");
-    stream.set_nonblocking(false).context("Failed to set stream to non-blocking")?;
+    // The daemon is responsible for the timeout, this just makes sure we don't wait forever if the daemon hangs entirely.
+    stream.set_read_timeout(Some(timeout + Duration::from_secs(1))).context("Failed to set read timeout")?;
+    send_request(stream, action, Some(timeout))?;
+
+    let response = match ipc_protocol::read_frame(stream) {
+        Ok(response) => response,
+        Err(err) if is_timeout_error(&err) => bail!("Timed out while waiting for a response from the daemon"),
+        Err(err) => return Err(err).context("Error reading response from server"),
+    };
+    response.map(|response| Encoding::Bincode.decode_response(&response)).transpose()
+}
 
-    let message_bytes = bincode::serialize(&action)?;
+fn is_timeout_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .map_or(false, |err| matches!(err.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut))
+}
 
-    stream.write(&(message_bytes.len() as u32).to_be_bytes()).context("Failed to send command size header to IPC stream")?;
+/// Connect to the daemon and send the given request, calling `on_response` for every response the daemon streams back,
+/// until the daemon closes the connection.
+pub fn do_server_subscription(
+    stream: &mut UnixStream,
+    action: &opts::ActionWithServer,
+    mut on_response: impl FnMut(DaemonResponse),
+) -> Result<()> {
+    log::debug!("Forwarding options to server, waiting for streamed responses");
+    send_request(stream, action, None)?;
 
-    stream.write_all(&message_bytes).context("Failed to write command to IPC stream")?;
+    while let Some(response) = ipc_protocol::read_frame(stream).context("Error reading response from server")? {
+        on_response(Encoding::Bincode.decode_response(&response)?);
+    }
+    Ok(())
+}
 
-    let mut buf = Vec::new();
-    stream.set_read_timeout(Some(std::time::Duration::from_millis(100))).context("Failed to set read timeout")?;
-    stream.read_to_end(&mut buf).context("Error reading response from server")?;
+/// Perform the protocol handshake and send the request to the daemon.
+/// Fails with a [`ipc_protocol::ProtocolMismatch`] if the daemon can't handle requests from this client.
+fn send_request(stream: &mut UnixStream, action: &opts::ActionWithServer, timeout: Option<Duration>) -> Result<()> {
+    stream.set_nonblocking(false).context("Failed to set stream to non-blocking")?;
+
+    let handshake = serde_json::to_vec(&Handshake::new(Encoding::Bincode))?;
+    stream.write_all(&ipc_protocol::frame(&handshake)).context("Failed to write handshake to IPC stream")?;
+    ipc_protocol::check_handshake_response(
+        ipc_protocol::read_frame(stream).context("Error reading handshake response from server")?,
+    )?;
 
-    Ok(if buf.is_empty() {
-        None
-    } else {
-        let buf = bincode::deserialize(&buf)?;
-        Some(buf)
-    })
+    let message_bytes = Encoding::Bincode.encode_request(action, timeout)?;
+    stream.write_all(&ipc_protocol::frame(&message_bytes)).context("Failed to write command to IPC stream")?;
+    Ok(())
 }
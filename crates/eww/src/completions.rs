@@ -0,0 +1,185 @@
+//! Shell completions for the eww cli.
+//!
+//! The completion scripts are generated by clap. On top of that, they complete the names of windows and variables,
+//! by calling the hidden `eww complete-names` command, which asks the running daemon for them,
+//! or reads the configuration if no daemon is running.
+
+use std::{collections::BTreeSet, os::unix::net::UnixStream, time::Duration};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{client, config, daemon_response::DaemonResponse, opts, paths::EwwPaths};
+
+/// How long completions wait for the daemon before falling back to reading the configuration.
+const DAEMON_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// The kind of names `eww complete-names` prints.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NameKind {
+    /// Names of windows, and ids of opened window instances
+    Windows,
+    /// Names of global variables
+    Variables,
+}
+
+/// Print the completion script for the given shell.
+pub fn print_completion_script(shell: CompletionShell) {
+    let generator = match shell {
+        CompletionShell::Bash => clap_complete::Shell::Bash,
+        CompletionShell::Zsh => clap_complete::Shell::Zsh,
+        CompletionShell::Fish => clap_complete::Shell::Fish,
+    };
+    clap_complete::generate(generator, &mut opts::cli_command(), "eww", &mut std::io::stdout());
+    let dynamic_completions = match shell {
+        CompletionShell::Bash => BASH_NAME_COMPLETIONS,
+        CompletionShell::Zsh => ZSH_NAME_COMPLETIONS,
+        CompletionShell::Fish => FISH_NAME_COMPLETIONS,
+    };
+    println!("{}", dynamic_completions);
+}
+
+/// Print the names of the given kind, one per line.
+pub fn print_names(paths: &EwwPaths, kind: NameKind) -> Result<()> {
+    let names = match names_from_daemon(paths, kind) {
+        Ok(names) => names,
+        Err(err) => {
+            log::debug!("Failed to get names from the daemon, reading the configuration instead: {:?}", err);
+            names_from_config(paths, kind)?
+        }
+    };
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn names_from_daemon(paths: &EwwPaths, kind: NameKind) -> Result<BTreeSet<String>> {
+    let mut stream = UnixStream::connect(paths.get_ipc_socket_file())?;
+    let action = match kind {
+        NameKind::Windows => opts::ActionWithServer::ShowWindows { json: true },
+        NameKind::Variables => opts::ActionWithServer::ShowState { all: true, json: true },
+    };
+    let json = match client::do_server_call(&mut stream, &action, DAEMON_TIMEOUT)? {
+        Some(DaemonResponse::Json(json)) => json,
+        other => bail!("Unexpected response from the daemon: {:?}", other),
+    };
+    Ok(names_from_json(kind, serde_json::from_str(&json)?))
+}
+
+/// Extract the names from the output of `eww windows --json` or `eww state --all --json`.
+fn names_from_json(kind: NameKind, json: serde_json::Value) -> BTreeSet<String> {
+    match (kind, json) {
+        (NameKind::Windows, serde_json::Value::Array(windows)) => windows
+            .iter()
+            .flat_map(|window| [window.get("name"), window.get("id")])
+            .filter_map(|name| name.and_then(|name| name.as_str()).map(str::to_string))
+            .collect(),
+        (NameKind::Variables, serde_json::Value::Object(vars)) => vars.into_iter().map(|(name, _)| name).collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+fn names_from_config(paths: &EwwPaths, kind: NameKind) -> Result<BTreeSet<String>> {
+    let config = config::read_from_eww_paths(paths)?;
+    Ok(match kind {
+        NameKind::Windows => config.get_windows().keys().cloned().collect(),
+        NameKind::Variables => config.get_global_var_names().map(|name| name.to_string()).collect(),
+    })
+}
+
+const BASH_NAME_COMPLETIONS: &str = r#"
+_eww_with_names() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local subcommand="" kind="" i
+    local -a global_args=()
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            -c|--config|--instance) global_args+=("${COMP_WORDS[i]}" "${COMP_WORDS[i+1]}"); ((i++)) ;;
+            --timeout) ((i++)) ;;
+            -*) ;;
+            *) subcommand="${COMP_WORDS[i]}"; break ;;
+        esac
+    done
+    case "$subcommand" in
+        open|o|close|c|open-many) kind=windows ;;
+        update|u|get|subscribe) kind=variables ;;
+    esac
+    if [[ -n "$kind" && "$cur" != -* && "$prev" != -* && "$prev" != "=" ]]; then
+        COMPREPLY=($(compgen -W "$(eww "${global_args[@]}" complete-names "$kind" 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    _eww "$@"
+}
+complete -F _eww_with_names -o bashdefault -o default eww"#;
+
+const ZSH_NAME_COMPLETIONS: &str = r#"
+_eww_with_names() {
+    local subcommand kind i
+    local -a global_args names
+    for ((i = 2; i < CURRENT; i++)); do
+        case "${words[i]}" in
+            -c|--config|--instance) global_args+=("${words[i]}" "${words[i+1]}"); ((i++)) ;;
+            --timeout) ((i++)) ;;
+            -*) ;;
+            *) subcommand="${words[i]}"; break ;;
+        esac
+    done
+    case "$subcommand" in
+        open|o|close|c|open-many) kind=windows ;;
+        update|u|get|subscribe) kind=variables ;;
+    esac
+    if [[ -n "$kind" && "${words[CURRENT]}" != -* && "${words[CURRENT-1]}" != -* && "${words[CURRENT]}" != *=* ]]; then
+        names=(${(f)"$(eww "${global_args[@]}" complete-names "$kind" 2>/dev/null)"})
+        compadd -a names
+        return
+    fi
+    _eww "$@"
+}
+compdef _eww_with_names eww"#;
+
+const FISH_NAME_COMPLETIONS: &str = r#"
+function __eww_names
+    set -l tokens (commandline -opc)
+    set -l global_args
+    for i in (seq 2 (count $tokens))
+        if contains -- $tokens[$i] -c --config --instance
+            set -a global_args $tokens[$i] $tokens[(math $i + 1)]
+        end
+    end
+    eww $global_args complete-names $argv 2>/dev/null
+end
+complete -c eww -n "__fish_seen_subcommand_from open o close c open-many" -f -a "(__eww_names windows)"
+complete -c eww -n "__fish_seen_subcommand_from update u get subscribe" -f -a "(__eww_names variables)""#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_names_from_json() {
+        let windows = serde_json::json!([
+            { "name": "bar", "id": "bar", "open": true },
+            { "name": "bar", "id": "bar-2", "open": false },
+        ]);
+        assert_eq!(names_from_json(NameKind::Windows, windows), BTreeSet::from(["bar".to_string(), "bar-2".to_string()]));
+        let vars = serde_json::json!({ "volume": { "value": "12" }, "time": { "value": "12:00" } });
+        assert_eq!(names_from_json(NameKind::Variables, vars), BTreeSet::from(["time".to_string(), "volume".to_string()]));
+        assert!(names_from_json(NameKind::Variables, serde_json::json!([])).is_empty());
+    }
+
+    #[test]
+    fn test_completion_commands_exist() {
+        let command = opts::cli_command();
+        for subcommand in ["open", "close", "open-many", "update", "get", "subscribe", "complete-names"] {
+            assert!(command.find_subcommand(subcommand).is_some(), "missing subcommand {}", subcommand);
+        }
+    }
+}
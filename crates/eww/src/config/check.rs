@@ -0,0 +1,118 @@
+//! Static checks of the configuration, run by `eww check` without a daemon or display.
+
+use std::collections::HashSet;
+
+use eww_shared_util::VarName;
+use itertools::Itertools;
+use yuck::config::{
+    validate::{self, ValidationError},
+    Config,
+};
+
+use crate::{
+    config::{inbuilt, scss, EwwConfig},
+    error_handling_ctx,
+    paths::EwwPaths,
+    widgets::{build_widget, widget_definitions},
+};
+
+/// Check the configuration in the config dir of the given [`EwwPaths`], returning all errors that were found.
+/// Besides parsing and validating the yuck configuration, this compiles the stylesheet
+/// and checks the widget trees of all windows and widget definitions.
+pub fn check_config(eww_paths: &EwwPaths) -> Vec<anyhow::Error> {
+    error_handling_ctx::clear_files();
+    let mut errors = check_yuck(eww_paths);
+    if let Err(err) = scss::parse_scss_from_config(eww_paths.get_config_dir()) {
+        errors.push(err);
+    }
+    errors
+}
+
+fn check_yuck(eww_paths: &EwwPaths) -> Vec<anyhow::Error> {
+    let yuck_path = eww_paths.get_yuck_path();
+    if !yuck_path.exists() {
+        return vec![anyhow::anyhow!("The configuration file `{}` does not exist", yuck_path.display())];
+    }
+    let config = match Config::generate_from_main_file(&mut *error_handling_ctx::FILE_DATABASE.write().unwrap(), yuck_path) {
+        Ok(config) => config,
+        Err(errors) => return errors.0.into_iter().map(anyhow::Error::from).collect(),
+    };
+
+    let globals: HashSet<VarName> = inbuilt::INBUILT_VAR_NAMES
+        .iter()
+        .chain(inbuilt::MAGIC_CONSTANT_NAMES)
+        .map(|name| VarName::from(*name))
+        .chain(config.script_vars.keys().cloned())
+        .chain(config.var_definitions.keys().cloned())
+        .collect();
+    let widget_defs = &config.widget_definitions;
+
+    let mut errors: Vec<anyhow::Error> = Vec::new();
+    for (name, def) in widget_defs.iter().sorted_by_key(|(name, _)| *name) {
+        if widget_definitions::BUILTIN_WIDGET_NAMES.contains(&name.as_str()) {
+            errors.push(ValidationError::AccidentalBuiltinOverride(def.span, name.to_string()).into());
+            continue;
+        }
+        errors.extend(validate::validate_widget_definition(widget_defs, &globals, def).into_iter().map(anyhow::Error::from));
+        errors.extend(build_widget::check_widget_use(widget_defs, &def.widget, true));
+    }
+    for (_, def) in config.window_definitions.iter().sorted_by_key(|(name, _)| *name) {
+        errors.extend(validate::validate_window_definition(widget_defs, &globals, def).into_iter().map(anyhow::Error::from));
+        errors.extend(build_widget::check_widget_use(widget_defs, &def.widget, false));
+    }
+    for (_, def) in config.magic_var_definitions.iter().sorted_by_key(|(name, _)| *name) {
+        errors.extend(validate::validate_magic_var_definition(&globals, def).into_iter().map(anyhow::Error::from));
+    }
+
+    // Loading the configuration like the daemon does catches anything the checks above might have missed
+    if errors.is_empty() {
+        if let Err(err) = EwwConfig::read_from_dir(&mut error_handling_ctx::FILE_DATABASE.write().unwrap(), eww_paths) {
+            errors.push(err);
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use yuck::{
+        config::{widget_definition::WidgetDefinition, widget_use::WidgetUse},
+        parser::{self, from_ast::FromAst},
+    };
+
+    /// Messages of all errors `eww check` reports for the given widget use in a window.
+    fn check(widget: &str, widgets: &[&str]) -> Vec<String> {
+        let widget_defs: HashMap<_, _> = widgets
+            .iter()
+            .map(|code| WidgetDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap())
+            .map(|def| (def.name.clone(), def))
+            .collect();
+        let widget = WidgetUse::from_ast(parser::parse_string(0, widget).unwrap()).unwrap();
+        let validation_errors = validate::validate_variables_in_widget_use(&widget_defs, &HashSet::new(), &widget, false);
+        validation_errors
+            .into_iter()
+            .map(anyhow::Error::from)
+            .chain(build_widget::check_widget_use(&widget_defs, &widget, false))
+            .map(|err| err.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_check_widget_use() {
+        assert!(check(r#"(box (foo) (label :text "hi"))"#, &["(defwidget foo [] (box (children)))"]).is_empty());
+        assert_eq!(check("(box (bar) (baz))", &[]), ["referenced unknown widget `bar`", "referenced unknown widget `baz`"]);
+        assert_eq!(check("(button (box) (box))", &[]), ["button can only have one child"]);
+        assert_eq!(check("(centerbox (box) (box))", &[]), ["centerbox must contain exactly 3 elements"]);
+        assert_eq!(check("(box (children))", &[]), ["`children` can only be used within a widget definition"]);
+        assert_eq!(
+            check(r#"(for x in "[1]" (box))"#, &[]),
+            ["This widget can only be used as a child of some container widget such as box"]
+        );
+        assert_eq!(
+            check("(box (foo :a 1 :b 2) (label :text text))", &["(defwidget foo [a] (box))"]),
+            ["Unknown attribute `b` in use of widget `foo`", "No variable named `text` in scope"]
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use anyhow::{bail, Context, Result};
 use eww_shared_util::VarName;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use yuck::{
     config::{
         script_var_definition::ScriptVarDefinition, validate::ValidationError, widget_definition::WidgetDefinition,
-        window_definition::WindowDefinition, Config,
+        widget_use::WidgetUse, window_definition::WindowDefinition, Config,
     },
-    error::DiagError,
+    error::{DiagError, DiagErrors},
     format_diagnostic::ToDiagnostic,
 };
 
@@ -30,6 +30,8 @@ pub struct EwwConfig {
     windows: HashMap<String, WindowDefinition>,
     initial_variables: HashMap<VarName, DynVal>,
     script_vars: HashMap<VarName, ScriptVarDefinition>,
+    /// Variables defined with `:persist true`
+    persisted_vars: HashSet<VarName>,
 
     // map of variables to all pollvars which refer to them in their run-while-expression
     run_while_mentions: HashMap<VarName, Vec<VarName>>,
@@ -53,16 +55,16 @@ impl EwwConfig {
             .collect();
         yuck::config::validate::validate(&config, magic_globals)?;
 
-        for (name, def) in &config.widget_definitions {
-            if widget_definitions::BUILTIN_WIDGET_NAMES.contains(&name.as_str()) {
-                return Err(
-                    DiagError(ValidationError::AccidentalBuiltinOverride(def.span, name.to_string()).to_diagnostic()).into()
-                );
-            }
-        }
+        let builtin_overrides = config
+            .widget_definitions
+            .iter()
+            .filter(|(name, _)| widget_definitions::BUILTIN_WIDGET_NAMES.contains(&name.as_str()))
+            .map(|(name, def)| DiagError(ValidationError::AccidentalBuiltinOverride(def.span, name.to_string()).to_diagnostic()));
+        DiagErrors::from_errors(builtin_overrides.collect())?;
 
-        let Config { widget_definitions, window_definitions, mut var_definitions, mut script_vars } = config;
-        script_vars.extend(inbuilt::get_inbuilt_vars());
+        let Config { widget_definitions, window_definitions, mut var_definitions, mut script_vars, magic_var_definitions } =
+            config;
+        script_vars.extend(inbuilt::get_configured_inbuilt_vars(magic_var_definitions)?);
         var_definitions.extend(inbuilt::get_magic_constants(eww_paths));
 
         let mut run_while_mentions = HashMap::<VarName, Vec<VarName>>::new();
@@ -74,11 +76,14 @@ impl EwwConfig {
             }
         }
 
+        let persisted_vars = var_definitions.values().filter(|var| var.persist).map(|var| var.name.clone()).collect();
+
         Ok(EwwConfig {
             windows: window_definitions,
             widgets: widget_definitions,
             initial_variables: var_definitions.into_iter().map(|(k, v)| (k, v.initial_value)).collect(),
             script_vars,
+            persisted_vars,
             run_while_mentions,
         })
     }
@@ -94,6 +99,11 @@ impl EwwConfig {
         Ok(vars)
     }
 
+    /// Get the names of all global variables, including script vars.
+    pub fn get_global_var_names(&self) -> impl Iterator<Item = &VarName> {
+        self.initial_variables.keys().chain(self.script_vars.keys())
+    }
+
     pub fn get_windows(&self) -> &HashMap<String, WindowDefinition> {
         &self.windows
     }
@@ -108,10 +118,72 @@ impl EwwConfig {
         })
     }
 
+    pub fn get_script_vars(&self) -> &HashMap<VarName, ScriptVarDefinition> {
+        &self.script_vars
+    }
+
+    /// Check if a global variable is defined the same way in both configurations,
+    /// in which case its current value is still valid after switching between them.
+    pub fn has_same_definition_of(&self, other: &EwwConfig, name: &VarName) -> bool {
+        match (self.script_vars.get(name), other.script_vars.get(name)) {
+            (Some(a), Some(b)) => a.is_same_definition(b),
+            (None, None) => {
+                matches!((self.initial_variables.get(name), other.initial_variables.get(name)), (Some(a), Some(b)) if a == b)
+            }
+            _ => false,
+        }
+    }
+
+    /// Names of all script vars whose definition differs from the one in the `old` configuration, including newly added ones.
+    pub fn get_changed_script_vars(&self, old: &EwwConfig) -> Vec<VarName> {
+        self.script_vars
+            .iter()
+            .filter(|(name, def)| !old.script_vars.get(*name).map_or(false, |old_def| old_def.is_same_definition(def)))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Check if a window, including all widgets it uses, is defined the same way in both configurations,
+    /// in which case open instances of it don't need to be rebuilt when switching between them.
+    pub fn has_same_window_definition(&self, other: &EwwConfig, window_name: &str) -> bool {
+        match (self.window_fingerprint(window_name), other.window_fingerprint(window_name)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Json representation of a window definition and all widget definitions it transitively uses,
+    /// without any source locations, such that it only changes when the definitions actually change.
+    fn window_fingerprint(&self, window_name: &str) -> Option<serde_json::Value> {
+        let window = self.windows.get(window_name)?;
+        let mut used_widgets = BTreeMap::new();
+        let mut pending = vec![&window.widget];
+        while let Some(widget_use) = pending.pop() {
+            match widget_use {
+                WidgetUse::Basic(basic) => {
+                    pending.extend(&basic.children);
+                    if let Some(def) = self.widgets.get(&basic.name) {
+                        if used_widgets.insert(basic.name.as_str(), def).is_none() {
+                            pending.push(&def.widget);
+                        }
+                    }
+                }
+                WidgetUse::Loop(loop_use) => pending.push(&loop_use.body),
+                WidgetUse::Children(_) => {}
+            }
+        }
+        without_spans::to_value(&(window, used_widgets)).ok()
+    }
+
     pub fn get_script_var(&self, name: &VarName) -> Result<&ScriptVarDefinition> {
         self.script_vars.get(name).with_context(|| format!("No script var named '{}' exists", name))
     }
 
+    /// Names of all variables whose values should be saved and restored when the daemon restarts
+    pub fn get_persisted_vars(&self) -> &HashSet<VarName> {
+        &self.persisted_vars
+    }
+
     pub fn get_widget_definitions(&self) -> &HashMap<String, WidgetDefinition> {
         &self.widgets
     }
@@ -121,3 +193,305 @@ impl EwwConfig {
         self.run_while_mentions.get(name)
     }
 }
+
+/// A serializer into [`serde_json::Value`] that serializes every [`eww_shared_util::Span`] as `null`,
+/// such that values that only differ in where they were defined serialize to the same value.
+mod without_spans {
+    use serde::{
+        ser::{self, Serializer as _},
+        Serialize,
+    };
+    use serde_json::{Error, Map, Value};
+    use std::collections::BTreeMap;
+
+    pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+        value.serialize(SpanFreeSerializer)
+    }
+
+    struct SpanFreeSerializer;
+
+    fn wrap_variant(variant: &'static str, value: Value) -> Value {
+        Value::Object(Map::from_iter([(variant.to_string(), value)]))
+    }
+
+    macro_rules! forward_to_json {
+        ($($method:ident($ty:ty)),* $(,)?) => {
+            $(fn $method(self, v: $ty) -> Result<Value, Error> {
+                ser::Serializer::$method(serde_json::value::Serializer, v)
+            })*
+        };
+    }
+
+    impl ser::Serializer for SpanFreeSerializer {
+        type Error = Error;
+        type Ok = Value;
+        type SerializeMap = MapSerializer;
+        type SerializeSeq = SeqSerializer;
+        type SerializeStruct = MapSerializer;
+        type SerializeStructVariant = VariantSerializer<MapSerializer>;
+        type SerializeTuple = SeqSerializer;
+        type SerializeTupleStruct = SeqSerializer;
+        type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+
+        forward_to_json! {
+            serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+            serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64),
+            serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str), serialize_bytes(&[u8]),
+        }
+
+        fn serialize_none(self) -> Result<Value, Error> {
+            Ok(Value::Null)
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<Value, Error> {
+            Ok(Value::Null)
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+            Ok(Value::Null)
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Value, Error> {
+            Ok(Value::String(variant.to_string()))
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Value, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<Value, Error> {
+            Ok(wrap_variant(variant, value.serialize(self)?))
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer, Error> {
+            Ok(SeqSerializer { items: Some(Vec::new()) })
+        }
+
+        fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+            self.serialize_seq(Some(len))
+        }
+
+        fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+            if name == "Span" {
+                Ok(SeqSerializer { items: None })
+            } else {
+                self.serialize_seq(Some(len))
+            }
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<VariantSerializer<SeqSerializer>, Error> {
+            Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, Error> {
+            Ok(MapSerializer { entries: BTreeMap::new(), next_key: None })
+        }
+
+        fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, Error> {
+            self.serialize_map(Some(len))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<VariantSerializer<MapSerializer>, Error> {
+            Ok(VariantSerializer { variant, inner: self.serialize_map(Some(len))? })
+        }
+    }
+
+    /// Collects the elements of a sequence, or skips them if `items` is `None`, which is used for spans.
+    struct SeqSerializer {
+        items: Option<Vec<Value>>,
+    }
+
+    impl ser::SerializeSeq for SeqSerializer {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            if let Some(items) = &mut self.items {
+                items.push(value.serialize(SpanFreeSerializer)?);
+            }
+            Ok(())
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            Ok(self.items.map(Value::Array).unwrap_or(Value::Null))
+        }
+    }
+
+    impl ser::SerializeTuple for SeqSerializer {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            ser::SerializeSeq::serialize_element(self, value)
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    impl ser::SerializeTupleStruct for SeqSerializer {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            ser::SerializeSeq::serialize_element(self, value)
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    /// Collects the entries of a map or struct, sorted by key such that the order of a `HashMap` doesn't matter.
+    struct MapSerializer {
+        entries: BTreeMap<String, Value>,
+        next_key: Option<String>,
+    }
+
+    impl ser::SerializeMap for MapSerializer {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+            self.next_key = Some(match key.serialize(SpanFreeSerializer)? {
+                Value::String(key) => key,
+                other => other.to_string(),
+            });
+            Ok(())
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            let key = self.next_key.take().ok_or_else(|| ser::Error::custom("map value serialized before its key"))?;
+            self.entries.insert(key, value.serialize(SpanFreeSerializer)?);
+            Ok(())
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            Ok(Value::Object(self.entries.into_iter().collect()))
+        }
+    }
+
+    impl ser::SerializeStruct for MapSerializer {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.entries.insert(key.to_string(), value.serialize(SpanFreeSerializer)?);
+            Ok(())
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            ser::SerializeMap::end(self)
+        }
+    }
+
+    /// Wraps the serialized fields of an enum variant in an object keyed by the name of the variant.
+    struct VariantSerializer<S> {
+        variant: &'static str,
+        inner: S,
+    }
+
+    impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            ser::SerializeSeq::serialize_element(&mut self.inner, value)
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            Ok(wrap_variant(self.variant, ser::SerializeSeq::end(self.inner)?))
+        }
+    }
+
+    impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+        type Error = Error;
+        type Ok = Value;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+        }
+
+        fn end(self) -> Result<Value, Error> {
+            Ok(wrap_variant(self.variant, ser::SerializeMap::end(self.inner)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yuck::{
+        config::script_var_definition::PollScriptVar,
+        parser::{self, from_ast::FromAst},
+    };
+
+    fn config_from(windows: &[&str], widgets: &[&str]) -> EwwConfig {
+        let windows = windows.iter().map(|code| WindowDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap());
+        let widgets = widgets.iter().map(|code| WidgetDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap());
+        EwwConfig {
+            windows: windows.map(|def| (def.name.clone(), def)).collect(),
+            widgets: widgets.map(|def| (def.name.clone(), def)).collect(),
+            ..EwwConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_has_same_window_definition() {
+        let config = config_from(
+            &[r#"(defwindow bar (foo))"#],
+            &[r#"(defwidget foo [] (label :text "hi"))"#, "(defwidget unused [] (box))"],
+        );
+        let moved = config_from(&[r#"(defwindow   bar   (foo))"#], &[r#"(defwidget   foo [] (label :text "hi"))"#]);
+        let changed = config_from(&[r#"(defwindow bar (foo))"#], &[r#"(defwidget foo [] (label :text "ho"))"#]);
+        assert!(config.has_same_window_definition(&moved, "bar"));
+        assert!(!config.has_same_window_definition(&changed, "bar"));
+        assert!(!config.has_same_window_definition(&moved, "baz"));
+    }
+
+    #[test]
+    fn test_serialize_without_spans() {
+        use eww_shared_util::Span;
+        let value = without_spans::to_value(&(Span(1, 2, 0), [1, 2, 3], Some(Span::DUMMY))).unwrap();
+        assert_eq!(value, serde_json::json!([null, [1, 2, 3], null]));
+    }
+
+    #[test]
+    fn test_get_changed_script_vars() {
+        let with_poll = |code| {
+            let mut config = config_from(&[r#"(defwindow bar (label :text time))"#], &[]);
+            let var = PollScriptVar::from_ast(parser::parse_string(0, code).unwrap()).unwrap();
+            config.script_vars.insert(var.name.clone(), ScriptVarDefinition::Poll(var));
+            config
+        };
+        let old = with_poll(r#"(defpoll time :interval "1s" "date +%H:%M")"#);
+        let edited = with_poll(r#"(defpoll time :interval "1s" "date +%H:%M:%S")"#);
+        // The window stays open when reloading, so the edited poll var has to be restarted on its own
+        assert!(edited.has_same_window_definition(&old, "bar"));
+        assert_eq!(edited.get_changed_script_vars(&old), vec![VarName::from("time")]);
+        assert!(old.get_changed_script_vars(&old).is_empty());
+    }
+}
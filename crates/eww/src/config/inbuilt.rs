@@ -1,16 +1,24 @@
 use std::{collections::HashMap, time::Duration};
 
+use itertools::Itertools;
 use simplexpr::{dynval::DynVal, SimplExpr};
-use yuck::config::{
-    script_var_definition::{PollScriptVar, ScriptVarDefinition, VarSource},
-    var_definition::VarDefinition,
+use yuck::{
+    config::{
+        magic_var_definition::MagicVarDefinition,
+        script_var_definition::{PollScriptVar, ScriptVarDefinition, VarSource},
+        var_definition::VarDefinition,
+    },
+    error::{DiagError, DiagErrors},
+    gen_diagnostic,
 };
 
 use crate::{config::system_stats::*, paths::EwwPaths};
 use eww_shared_util::VarName;
 
 macro_rules! define_builtin_vars {
-    ($interval:expr, $($name:literal => $fun:expr),*$(,)?) => {
+    (@interval $default_interval:expr) => { $default_interval };
+    (@interval $default_interval:expr, $interval:expr) => { $interval };
+    ($default_interval:expr, $($name:literal $([$interval:expr])? => $fun:expr),*$(,)?) => {
         pub static INBUILT_VAR_NAMES: &[&'static str] = &[$($name),*];
         pub fn get_inbuilt_vars() -> HashMap<VarName, ScriptVarDefinition> {
             maplit::hashmap! {
@@ -20,7 +28,7 @@ macro_rules! define_builtin_vars {
                     run_while_expr: SimplExpr::Literal(DynVal::from(true)),
                     command: VarSource::Function($fun),
                     initial_value: None,
-                    interval: $interval,
+                    interval: define_builtin_vars!(@interval $default_interval $(, $interval)?),
                     name_span: eww_shared_util::span::Span::DUMMY,
                 })
                 ),*
@@ -61,6 +69,36 @@ define_builtin_vars! { Duration::new(2, 0),
     // @desc EWW_NET - Bytes up/down on all interfaces
     // @prop { <name>: { up, down } }
     "EWW_NET" => || Ok(DynVal::from(net())),
+
+    // @desc EWW_TIME - the current UNIX timestamp, updated every second. Format it using `formattime`, i.e. `{formattime(EWW_TIME, "%H:%M")}`
+    "EWW_TIME" [Duration::from_secs(1)] => || Ok(DynVal::from(get_time()?)),
+}
+
+/// Get the inbuilt variables, with the intervals and run-while conditions configured using `defmagic` applied.
+pub fn get_configured_inbuilt_vars(
+    definitions: HashMap<VarName, MagicVarDefinition>,
+) -> Result<HashMap<VarName, ScriptVarDefinition>, DiagErrors> {
+    let mut vars = get_inbuilt_vars();
+    let mut errors = Vec::new();
+    for def in definitions.into_values() {
+        match vars.get_mut(&def.name) {
+            Some(ScriptVarDefinition::Poll(var)) => {
+                if let Some(interval) = def.interval {
+                    var.interval = interval;
+                }
+                if let Some(run_while_expr) = def.run_while_expr {
+                    var.run_while_expr = run_while_expr;
+                }
+            }
+            _ => errors.push(DiagError(gen_diagnostic! {
+                msg = format!("Unknown magic variable `{}`", def.name),
+                label = def.name_span,
+                note = format!("Must be one of: {}", INBUILT_VAR_NAMES.iter().join(", ")),
+            })),
+        }
+    }
+    DiagErrors::from_errors(errors)?;
+    Ok(vars)
 }
 
 macro_rules! define_magic_constants {
@@ -71,6 +109,7 @@ macro_rules! define_magic_constants {
                 $(VarName::from($name) => VarDefinition {
                     name: VarName::from($name),
                     initial_value: $value,
+                    persist: false,
                     span: eww_shared_util::span::Span::DUMMY
                 }),*
             }
@@ -81,11 +120,12 @@ define_magic_constants! { eww_paths,
     // @desc EWW_CONFIG_DIR - Path to the eww configuration of the current process
     "EWW_CONFIG_DIR" => DynVal::from_string(eww_paths.get_config_dir().to_string_lossy().into_owned()),
 
-    // @desc EWW_CMD - eww command running in the current configuration, useful in event handlers. I.e.: `:onclick "${EWW_CMD} update foo=bar"`
+    // @desc EWW_CMD - eww command running in the current configuration and instance, useful in event handlers. I.e.: `:onclick "${EWW_CMD} update foo=bar"`
     "EWW_CMD" => DynVal::from_string(
-        format!("\"{}\" --config \"{}\"",
+        format!("\"{}\" --config \"{}\"{}",
             std::env::current_exe().map(|x| x.to_string_lossy().into_owned()).unwrap_or_else(|_| "eww".to_string()),
-            eww_paths.get_config_dir().to_string_lossy().into_owned()
+            eww_paths.get_config_dir().to_string_lossy().into_owned(),
+            eww_paths.get_instance().map(|instance| format!(" --instance \"{}\"", instance)).unwrap_or_default()
         )
     ),
     // @desc EWW_EXECUTABLE - Full path of the eww executable
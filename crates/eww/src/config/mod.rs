@@ -1,3 +1,4 @@
+pub mod check;
 pub mod eww_config;
 pub mod inbuilt;
 pub mod script_var;
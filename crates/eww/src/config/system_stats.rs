@@ -177,3 +177,9 @@ pub fn net() -> String {
     );
     interfaces
 }
+
+pub fn get_time() -> Result<String> {
+    let now =
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).context("System time is before the unix epoch")?;
+    Ok(now.as_secs().to_string())
+}
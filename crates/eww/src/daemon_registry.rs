@@ -0,0 +1,101 @@
+//! Registry of the eww daemons that are currently running, used by `eww list-daemons`.
+//!
+//! Every daemon writes a small json file describing itself into a directory in `XDG_RUNTIME_DIR` when it starts,
+//! and removes it again when it stops. Entries of daemons that didn't shut down cleanly are removed when listing the daemons.
+
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{self, EwwPaths};
+
+/// Information about a running eww daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    /// Name given to the daemon with `--instance`, if any
+    pub instance: Option<String>,
+    pub daemon_id: String,
+    pub pid: u32,
+    pub config_dir: PathBuf,
+    pub ipc_socket_file: PathBuf,
+    /// Time the daemon was started at, in seconds since the unix epoch
+    pub started_at: u64,
+}
+
+impl DaemonInfo {
+    /// Time since the daemon was started, in seconds.
+    pub fn uptime_secs(&self) -> u64 {
+        unix_time_now().saturating_sub(self.started_at)
+    }
+
+    fn is_running(&self) -> bool {
+        let pid = nix::unistd::Pid::from_raw(self.pid as i32);
+        // Sending no signal only checks if the process exists. EPERM means it exists, but belongs to another user.
+        let process_exists = match nix::sys::signal::kill(pid, None) {
+            Ok(()) => true,
+            Err(err) => err == nix::errno::Errno::EPERM,
+        };
+        process_exists && self.ipc_socket_file.exists()
+    }
+}
+
+fn registry_dir() -> PathBuf {
+    paths::runtime_dir().join("eww-daemons")
+}
+
+fn entry_file(daemon_id: &str) -> PathBuf {
+    registry_dir().join(format!("{}.json", daemon_id))
+}
+
+fn unix_time_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or_default()
+}
+
+/// Add the current process to the registry, as the daemon for the given paths.
+pub fn register(paths: &EwwPaths) -> Result<()> {
+    let info = DaemonInfo {
+        instance: paths.get_instance().map(str::to_string),
+        daemon_id: paths.get_daemon_id().to_string(),
+        pid: std::process::id(),
+        config_dir: paths.get_config_dir().to_path_buf(),
+        ipc_socket_file: paths.get_ipc_socket_file().to_path_buf(),
+        started_at: unix_time_now(),
+    };
+    std::fs::create_dir_all(registry_dir()).context("Failed to create daemon registry directory")?;
+    let file = entry_file(&info.daemon_id);
+    std::fs::write(&file, serde_json::to_string(&info)?).with_context(|| format!("Failed to write {}", file.display()))
+}
+
+/// Remove the daemon for the given paths from the registry.
+pub fn unregister(paths: &EwwPaths) {
+    let _ = std::fs::remove_file(entry_file(paths.get_daemon_id()));
+}
+
+/// Get all daemons that are currently running, sorted by their name.
+/// Entries of daemons that are no longer running are removed from the registry.
+pub fn list_running() -> Result<Vec<DaemonInfo>> {
+    let entries = match std::fs::read_dir(registry_dir()) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).context("Failed to read daemon registry directory"),
+    };
+    let mut daemons = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let info: Option<DaemonInfo> =
+            std::fs::read_to_string(&path).ok().and_then(|content| serde_json::from_str(&content).ok());
+        match info {
+            Some(info) if info.is_running() => daemons.push(info),
+            _ => {
+                log::debug!("Removing stale daemon registry entry {}", path.display());
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    daemons.sort_by(|a, b| (&a.instance, &a.daemon_id).cmp(&(&b.instance, &b.daemon_id)));
+    Ok(daemons)
+}
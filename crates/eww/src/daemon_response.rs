@@ -16,6 +16,9 @@ use crate::error_handling_ctx;
 pub enum DaemonResponse {
     Success(String),
     Failure(String),
+    /// Structured output for commands run with `--json`.
+    /// The payload is already encoded as json, as arbitrary json values can't be sent through bincode.
+    Json(String),
 }
 
 #[derive(Debug)]
@@ -35,6 +38,11 @@ impl DaemonResponseSender {
         self.0.send(DaemonResponse::Failure(s)).context("Failed to send failure response from application thread")
     }
 
+    pub fn send_json(&self, value: serde_json::Value) -> Result<()> {
+        let json = serde_json::to_string(&value).context("Failed to serialize json response")?;
+        self.0.send(DaemonResponse::Json(json)).context("Failed to send json response from application thread")
+    }
+
     /// Given a list of errors, respond with an error value if there are any errors, and respond with success otherwise.
     pub fn respond_with_error_list(&self, errors: impl IntoIterator<Item = anyhow::Error>) -> Result<()> {
         let errors = errors.into_iter().map(|e| error_handling_ctx::format_error(&e)).join("\n");
@@ -45,6 +53,18 @@ impl DaemonResponseSender {
         }
     }
 
+    /// Send the output of a successful result, or the error message in case of an Err.
+    pub fn respond_with_output(&self, result: Result<String>) -> Result<()> {
+        match result {
+            Ok(output) => self.send_success(output),
+            Err(e) => {
+                let formatted = error_handling_ctx::format_error(&e);
+                self.respond_with_error_msg(formatted)
+            }
+        }
+        .context("sending response from main thread")
+    }
+
     /// In case of an Err, send the error message to a sender.
     pub fn respond_with_result<T>(&self, result: Result<T>) -> Result<()> {
         match result {
@@ -57,6 +77,16 @@ impl DaemonResponseSender {
         .context("sending response from main thread")
     }
 
+    /// Wait until the receiving end of this sender is dropped, i.e. because the client disconnected.
+    pub fn closed(&self) -> impl std::future::Future<Output = ()> {
+        let sender = self.0.clone();
+        async move { sender.closed().await }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
     fn respond_with_error_msg(&self, msg: String) -> Result<()> {
         println!("Action failed with error: {}", msg);
         self.send_failure(msg)
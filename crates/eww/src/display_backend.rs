@@ -1,4 +1,4 @@
-use yuck::config::window_definition::WindowDefinition;
+use crate::window_initiator::WindowInitiator;
 
 #[cfg(feature = "wayland")]
 pub use platform_wayland::WaylandBackend;
@@ -7,13 +7,13 @@ pub use platform_wayland::WaylandBackend;
 pub use platform_x11::{set_xprops, X11Backend};
 
 pub trait DisplayBackend: Send + Sync + 'static {
-    fn initialize_window(window_def: &WindowDefinition, monitor: gdk::Rectangle) -> Option<gtk::Window>;
+    fn initialize_window(window_init: &WindowInitiator, monitor: gdk::Rectangle) -> Option<gtk::Window>;
 }
 
 pub struct NoBackend;
 
 impl DisplayBackend for NoBackend {
-    fn initialize_window(_window_def: &WindowDefinition, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
+    fn initialize_window(_window_init: &WindowInitiator, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
         Some(gtk::Window::new(gtk::WindowType::Toplevel))
     }
 }
@@ -21,22 +21,20 @@ impl DisplayBackend for NoBackend {
 #[cfg(feature = "wayland")]
 mod platform_wayland {
     use gtk::prelude::*;
-    use yuck::config::{
-        window_definition::{WindowDefinition, WindowStacking},
-        window_geometry::AnchorAlignment,
-    };
+    use yuck::config::{window_definition::WindowStacking, window_geometry::AnchorAlignment};
 
     use super::DisplayBackend;
+    use crate::window_initiator::WindowInitiator;
 
     pub struct WaylandBackend;
 
     impl DisplayBackend for WaylandBackend {
-        fn initialize_window(window_def: &WindowDefinition, monitor: gdk::Rectangle) -> Option<gtk::Window> {
+        fn initialize_window(window_init: &WindowInitiator, monitor: gdk::Rectangle) -> Option<gtk::Window> {
             let window = gtk::Window::new(gtk::WindowType::Toplevel);
             // Initialising a layer shell surface
             gtk_layer_shell::init_for_window(&window);
             // Sets the monitor where the surface is shown
-            if let Some(ident) = window_def.monitor.clone() {
+            if let Some(ident) = window_init.monitor.clone() {
                 let display = gdk::Display::default().expect("could not get default display");
                 if let Some(monitor) = crate::app::get_monitor_from_display(&display, &ident) {
                     gtk_layer_shell::set_monitor(&window, &monitor);
@@ -44,24 +42,24 @@ mod platform_wayland {
                     return None;
                 }
             };
-            window.set_resizable(window_def.resizable);
+            window.set_resizable(window_init.resizable);
 
             // Sets the layer where the layer shell surface will spawn
-            match window_def.stacking {
+            match window_init.stacking {
                 WindowStacking::Foreground => gtk_layer_shell::set_layer(&window, gtk_layer_shell::Layer::Top),
                 WindowStacking::Background => gtk_layer_shell::set_layer(&window, gtk_layer_shell::Layer::Background),
                 WindowStacking::Bottom => gtk_layer_shell::set_layer(&window, gtk_layer_shell::Layer::Bottom),
                 WindowStacking::Overlay => gtk_layer_shell::set_layer(&window, gtk_layer_shell::Layer::Overlay),
             }
 
-            if let Some(namespace) = &window_def.backend_options.wayland.namespace {
+            if let Some(namespace) = &window_init.backend_options.wayland.namespace {
                 gtk_layer_shell::set_namespace(&window, namespace);
             }
 
             // Sets the keyboard interactivity
-            gtk_layer_shell::set_keyboard_interactivity(&window, window_def.backend_options.wayland.focusable);
+            gtk_layer_shell::set_keyboard_interactivity(&window, window_init.backend_options.wayland.focusable);
 
-            if let Some(geometry) = window_def.geometry {
+            if let Some(geometry) = window_init.geometry {
                 // Positioning surface
                 let mut top = false;
                 let mut left = false;
@@ -98,7 +96,7 @@ mod platform_wayland {
                     gtk_layer_shell::set_margin(&window, gtk_layer_shell::Edge::Top, yoffset);
                 }
             }
-            if window_def.backend_options.wayland.exclusive {
+            if window_init.backend_options.wayland.exclusive {
                 gtk_layer_shell::auto_exclusive_zone_enable(&window);
             }
             Some(window)
@@ -120,24 +118,25 @@ mod platform_x11 {
     };
     use yuck::config::{
         backend_window_options::{Side, X11WindowType},
-        window_definition::{WindowDefinition, WindowStacking},
+        window_definition::WindowStacking,
     };
 
     use super::DisplayBackend;
+    use crate::window_initiator::WindowInitiator;
 
     pub struct X11Backend;
     impl DisplayBackend for X11Backend {
-        fn initialize_window(window_def: &WindowDefinition, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
+        fn initialize_window(window_init: &WindowInitiator, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
             let window_type =
-                if window_def.backend_options.x11.wm_ignore { gtk::WindowType::Popup } else { gtk::WindowType::Toplevel };
+                if window_init.backend_options.x11.wm_ignore { gtk::WindowType::Popup } else { gtk::WindowType::Toplevel };
             let window = gtk::Window::new(window_type);
-            let wm_class_name = format!("eww-{}", window_def.name);
+            let wm_class_name = format!("eww-{}", window_init.name);
             #[allow(deprecated)]
             window.set_wmclass(&wm_class_name, &wm_class_name);
-            window.set_resizable(window_def.resizable);
-            window.set_keep_above(window_def.stacking == WindowStacking::Foreground);
-            window.set_keep_below(window_def.stacking == WindowStacking::Background);
-            if window_def.backend_options.x11.sticky {
+            window.set_resizable(window_init.resizable);
+            window.set_keep_above(window_init.stacking == WindowStacking::Foreground);
+            window.set_keep_below(window_init.stacking == WindowStacking::Background);
+            if window_init.backend_options.x11.sticky {
                 window.stick();
             } else {
                 window.unstick();
@@ -146,9 +145,9 @@ mod platform_x11 {
         }
     }
 
-    pub fn set_xprops(window: &gtk::Window, monitor: gdk::Rectangle, window_def: &WindowDefinition) -> Result<()> {
+    pub fn set_xprops(window: &gtk::Window, monitor: gdk::Rectangle, window_init: &WindowInitiator) -> Result<()> {
         let backend = X11BackendConnection::new()?;
-        backend.set_xprops_for(window, monitor, window_def)?;
+        backend.set_xprops_for(window, monitor, window_init)?;
         Ok(())
     }
 
@@ -170,12 +169,12 @@ mod platform_x11 {
             &self,
             window: &gtk::Window,
             monitor_rect: gdk::Rectangle,
-            window_def: &WindowDefinition,
+            window_init: &WindowInitiator,
         ) -> Result<()> {
             let gdk_window = window.window().context("Couldn't get gdk window from gtk window")?;
             let win_id =
                 gdk_window.downcast_ref::<gdkx11::X11Window>().context("Failed to get x11 window for gtk window")?.xid() as u32;
-            let strut_def = window_def.backend_options.x11.struts;
+            let strut_def = window_init.backend_options.x11.struts;
             let root_window_geometry = self.conn.get_geometry(self.root_window)?.reply()?;
 
             let mon_end_x = (monitor_rect.x() + monitor_rect.width()) as u32 - 1u32;
@@ -229,7 +228,7 @@ mod platform_x11 {
                 win_id,
                 self.atoms._NET_WM_WINDOW_TYPE,
                 self.atoms.ATOM,
-                &[match window_def.backend_options.x11.window_type {
+                &[match window_init.backend_options.x11.window_type {
                     X11WindowType::Dock => self.atoms._NET_WM_WINDOW_TYPE_DOCK,
                     X11WindowType::Normal => self.atoms._NET_WM_WINDOW_TYPE_NORMAL,
                     X11WindowType::Dialog => self.atoms._NET_WM_WINDOW_TYPE_DIALOG,
@@ -10,7 +10,12 @@ use codespan_reporting::{
 use eww_shared_util::Span;
 use once_cell::sync::Lazy;
 use simplexpr::{dynval::ConversionError, eval::EvalError};
-use yuck::{config::validate::ValidationError, error::DiagError, format_diagnostic::ToDiagnostic};
+use yuck::{
+    config::validate::ValidationError,
+    error::{DiagError, DiagErrors},
+    format_diagnostic::ToDiagnostic,
+    gen_diagnostic,
+};
 
 use crate::file_database::FileDatabase;
 
@@ -21,11 +26,9 @@ pub fn clear_files() {
 }
 
 pub fn print_error(err: anyhow::Error) {
-    match anyhow_err_to_diagnostic(&err) {
-        Some(diag) => match stringify_diagnostic(diag) {
-            Ok(diag) => eprintln!("{}", diag),
-            Err(_) => log::error!("{:?}", err),
-        },
+    match stringify_error(&err) {
+        Some(diag) if crate::logging::is_logging_to_file() => log::error!("{}", diag),
+        Some(diag) => eprintln!("{}", diag),
         None => log::error!("{:?}", err),
     }
 }
@@ -34,7 +37,16 @@ pub fn format_error(err: &anyhow::Error) -> String {
     for err in err.chain() {
         format!("chain: {}", err);
     }
-    anyhow_err_to_diagnostic(err).and_then(|diag| stringify_diagnostic(diag).ok()).unwrap_or_else(|| format!("{:?}", err))
+    stringify_error(err).unwrap_or_else(|| format!("{:?}", err))
+}
+
+/// Render all diagnostics of the given error, or None if it isn't a diagnostic error.
+fn stringify_error(err: &anyhow::Error) -> Option<String> {
+    let diagnostics = anyhow_err_to_diagnostics(err);
+    if diagnostics.is_empty() {
+        return None;
+    }
+    diagnostics.into_iter().map(stringify_diagnostic).collect::<anyhow::Result<String>>().ok()
 }
 
 pub fn anyhow_err_to_diagnostic(err: &anyhow::Error) -> Option<Diagnostic<usize>> {
@@ -52,6 +64,30 @@ pub fn anyhow_err_to_diagnostic(err: &anyhow::Error) -> Option<Diagnostic<usize>
     }
 }
 
+/// Get all diagnostics of the given error. Errors that combine multiple errors, like [`DiagErrors`], have multiple.
+pub fn anyhow_err_to_diagnostics(err: &anyhow::Error) -> Vec<Diagnostic<usize>> {
+    match err.downcast_ref::<DiagErrors>() {
+        Some(errors) => errors.0.iter().map(|err| err.0.clone()).collect(),
+        None => anyhow_err_to_diagnostic(err).into_iter().collect(),
+    }
+}
+
+/// Combine the given errors into a single one, which is reported as all of the individual errors.
+pub fn combine_errors(mut errors: Vec<anyhow::Error>) -> anyhow::Result<()> {
+    if errors.len() <= 1 {
+        return errors.pop().map_or(Ok(()), Err);
+    }
+    let diagnostics = errors.iter().flat_map(|err| {
+        let diagnostics = anyhow_err_to_diagnostics(err);
+        if diagnostics.is_empty() {
+            vec![gen_diagnostic!(format!("{:?}", err))]
+        } else {
+            diagnostics
+        }
+    });
+    Err(DiagErrors(diagnostics.map(DiagError).collect()).into())
+}
+
 pub fn stringify_diagnostic(mut diagnostic: codespan_reporting::diagnostic::Diagnostic<usize>) -> anyhow::Result<String> {
     diagnostic.labels.drain_filter(|label| Span(label.range.start, label.range.end, label.file_id).is_dummy());
 
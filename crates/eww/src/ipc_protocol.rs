@@ -0,0 +1,284 @@
+//! The protocol spoken between eww clients and the eww daemon over the IPC socket.
+//!
+//! Every message is sent as a frame: the size of the payload as a big-endian `u32`, followed by the payload itself.
+//!
+//! 1. The client starts by sending a [`Handshake`], which is always encoded as json.
+//! 2. The daemon answers with a json encoded [`HandshakeResponse`]. If the handshake was rejected, the connection is closed.
+//! 3. The client sends a single [`Request`], in the encoding it chose in the handshake.
+//! 4. The daemon sends zero or more responses in that same encoding, and then closes the connection.
+//!
+//! The bincode encoding is what the eww CLI uses. As it directly mirrors eww's internal types,
+//! it is only accepted from a client with the exact same eww version as the daemon.
+//! The json encoding is meant for external tools, and only requires the protocol version to match.
+//! See `docs/src/ipc.md` for a description of the json messages.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{io::Read, time::Duration};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{daemon_response::DaemonResponse, opts::ActionWithServer};
+
+/// Version of the IPC protocol. This needs to be incremented whenever the json representation of any message changes.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Largest frame that is accepted, to avoid allocating arbitrary amounts of memory for a size header sent by a client.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Version of eww itself, which the bincode encoding is tied to.
+pub const EWW_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Error returned when the client and daemon can't talk to each other, because they use different versions of eww or the protocol.
+#[derive(Debug, derive_more::Display)]
+pub struct ProtocolMismatch(pub String);
+
+impl std::error::Error for ProtocolMismatch {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Bincode,
+    Json,
+}
+
+/// First message of every connection, sent by the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol_version: u32,
+    pub encoding: Encoding,
+    /// The eww version of the client. This is only required when using the bincode encoding.
+    #[serde(default)]
+    pub eww_version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HandshakeResponse {
+    Accepted { protocol_version: u32, eww_version: String },
+    Rejected { protocol_version: u32, eww_version: String, reason: String },
+}
+
+impl Handshake {
+    pub fn new(encoding: Encoding) -> Self {
+        Handshake { protocol_version: PROTOCOL_VERSION, encoding, eww_version: Some(EWW_VERSION.to_string()) }
+    }
+
+    /// Decide if the daemon can talk to the client that sent this handshake.
+    pub fn respond(&self) -> HandshakeResponse {
+        let reject = |reason: String| HandshakeResponse::Rejected {
+            protocol_version: PROTOCOL_VERSION,
+            eww_version: EWW_VERSION.to_string(),
+            reason,
+        };
+        if self.protocol_version != PROTOCOL_VERSION {
+            return reject(format!(
+                "The eww daemon speaks IPC protocol version {}, but the client uses version {}.",
+                PROTOCOL_VERSION, self.protocol_version
+            ));
+        }
+        match (self.encoding, &self.eww_version) {
+            (Encoding::Bincode, Some(version)) if version != EWW_VERSION => reject(format!(
+                "The eww daemon is running version {}, but the client is version {}. Restart the daemon (`eww kill`) to use the \
+                 new version.",
+                EWW_VERSION, version
+            )),
+            (Encoding::Bincode, None) => reject("The bincode encoding requires the client to send its eww_version".to_string()),
+            _ => HandshakeResponse::Accepted { protocol_version: PROTOCOL_VERSION, eww_version: EWW_VERSION.to_string() },
+        }
+    }
+}
+
+/// Message sent by the client after a successful handshake.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Request {
+    pub action: ActionWithServer,
+    /// How long the daemon should wait for the command to finish before responding with a failure.
+    /// Without a timeout, the daemon waits until the command is done.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Borrowing counterpart of [`Request`], used for sending.
+#[derive(Serialize)]
+struct RequestRef<'a> {
+    action: &'a ActionWithServer,
+    timeout_ms: Option<u64>,
+}
+
+/// Json representation of a [`DaemonResponse`].
+/// The bincode encoding sends the [`DaemonResponse`] as is.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum JsonResponse {
+    Success { output: String },
+    Failure { error: String },
+    Json { value: serde_json::Value },
+}
+
+impl From<DaemonResponse> for JsonResponse {
+    fn from(response: DaemonResponse) -> Self {
+        match response {
+            DaemonResponse::Success(output) => JsonResponse::Success { output },
+            DaemonResponse::Failure(error) => JsonResponse::Failure { error },
+            DaemonResponse::Json(json) => match serde_json::from_str(&json) {
+                Ok(value) => JsonResponse::Json { value },
+                Err(_) => JsonResponse::Json { value: serde_json::Value::String(json) },
+            },
+        }
+    }
+}
+
+impl From<JsonResponse> for DaemonResponse {
+    fn from(response: JsonResponse) -> Self {
+        match response {
+            JsonResponse::Success { output } => DaemonResponse::Success(output),
+            JsonResponse::Failure { error } => DaemonResponse::Failure(error),
+            JsonResponse::Json { value } => DaemonResponse::Json(value.to_string()),
+        }
+    }
+}
+
+impl Encoding {
+    pub fn encode_request(self, action: &ActionWithServer, timeout: Option<Duration>) -> Result<Vec<u8>> {
+        let request = RequestRef { action, timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64) };
+        Ok(match self {
+            Encoding::Bincode => bincode::serialize(&request)?,
+            Encoding::Json => serde_json::to_vec(&request)?,
+        })
+    }
+
+    pub fn decode_request(self, bytes: &[u8]) -> Result<Request> {
+        match self {
+            Encoding::Bincode => bincode::deserialize(bytes).context("Failed to parse bincode request"),
+            Encoding::Json => serde_json::from_slice(bytes).context("Failed to parse json request"),
+        }
+    }
+
+    pub fn encode_response(self, response: DaemonResponse) -> Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Bincode => bincode::serialize(&response)?,
+            Encoding::Json => serde_json::to_vec(&JsonResponse::from(response))?,
+        })
+    }
+
+    pub fn decode_response(self, bytes: &[u8]) -> Result<DaemonResponse> {
+        match self {
+            Encoding::Bincode => bincode::deserialize(bytes).context("Failed to parse bincode response"),
+            Encoding::Json => Ok(serde_json::from_slice::<JsonResponse>(bytes).context("Failed to parse json response")?.into()),
+        }
+    }
+}
+
+/// Prefix the payload with its size, turning it into a frame that can be sent over the socket.
+pub fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut message = (payload.len() as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Read a single frame, returning its payload.
+/// Returns `None` if the stream was closed before a new frame started.
+pub fn read_frame(stream: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut size_header = [0u8; 4];
+    match stream.read_exact(&mut size_header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read message size header from IPC stream"),
+    }
+    let mut payload = vec![0u8; frame_size(size_header)?];
+    stream.read_exact(&mut payload).context("Failed to read message from IPC stream")?;
+    Ok(Some(payload))
+}
+
+/// Async version of [`read_frame`].
+pub async fn read_frame_async(stream: &mut (impl AsyncRead + Unpin)) -> Result<Option<Vec<u8>>> {
+    let mut size_header = [0u8; 4];
+    match stream.read_exact(&mut size_header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read message size header from IPC stream"),
+    }
+    let mut payload = vec![0u8; frame_size(size_header)?];
+    stream.read_exact(&mut payload).await.context("Failed to read message from IPC stream")?;
+    Ok(Some(payload))
+}
+
+/// Size of the payload announced by a frames size header, failing if it exceeds [`MAX_FRAME_SIZE`].
+fn frame_size(size_header: [u8; 4]) -> Result<usize> {
+    let size = u32::from_be_bytes(size_header) as usize;
+    if size > MAX_FRAME_SIZE {
+        bail!("Message of {} bytes exceeds the maximum size of {} bytes", size, MAX_FRAME_SIZE);
+    }
+    Ok(size)
+}
+
+/// Interpret the daemons answer to a [`Handshake`], failing with a [`ProtocolMismatch`] if the handshake was rejected.
+pub fn check_handshake_response(response: Option<Vec<u8>>) -> Result<()> {
+    let response = match response {
+        Some(response) => response,
+        None => bail!(ProtocolMismatch(
+            "The eww daemon closed the connection during the handshake. It is most likely running an older version of eww. \
+             Restart the daemon (`eww kill`) to use the new version."
+                .to_string()
+        )),
+    };
+    match serde_json::from_slice(&response).context("Failed to parse handshake response from daemon")? {
+        HandshakeResponse::Accepted { .. } => Ok(()),
+        HandshakeResponse::Rejected { reason, .. } => bail!(ProtocolMismatch(reason)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_handshake_versions() {
+        assert!(matches!(Handshake::new(Encoding::Bincode).respond(), HandshakeResponse::Accepted { .. }));
+
+        let other_eww_version =
+            Handshake { protocol_version: PROTOCOL_VERSION, encoding: Encoding::Bincode, eww_version: Some("0.0.0".to_string()) };
+        assert!(matches!(other_eww_version.respond(), HandshakeResponse::Rejected { .. }));
+
+        let json = Handshake { protocol_version: PROTOCOL_VERSION, encoding: Encoding::Json, eww_version: None };
+        assert!(matches!(json.respond(), HandshakeResponse::Accepted { .. }));
+
+        let other_protocol = Handshake { protocol_version: PROTOCOL_VERSION + 1, encoding: Encoding::Json, eww_version: None };
+        assert!(matches!(other_protocol.respond(), HandshakeResponse::Rejected { .. }));
+    }
+
+    #[test]
+    fn test_json_request() {
+        let request = Encoding::Json.decode_request(br#"{"action": {"get": {"name": "foo"}}}"#).unwrap();
+        assert_eq!(request.action, ActionWithServer::GetVar { name: "foo".to_string(), json: false });
+
+        let request = Encoding::Json.decode_request(br#"{"action": {"update": {"mappings": [["foo", "bar"]]}}}"#).unwrap();
+        match request.action {
+            ActionWithServer::Update { mappings, .. } => {
+                assert_eq!(mappings.len(), 1);
+                assert_eq!(mappings[0].0, "foo".into());
+                assert_eq!(mappings[0].1.as_string().unwrap(), "bar");
+            }
+            other => panic!("Parsed wrong action: {:?}", other),
+        }
+
+        let request = Encoding::Json.decode_request(br#"{"action": "ping"}"#).unwrap();
+        assert_eq!(request.action, ActionWithServer::Ping);
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let message = frame(b"hello");
+        assert_eq!(read_frame(&mut message.as_slice()).unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(read_frame(&mut [].as_slice()).unwrap(), None);
+        let oversized = (MAX_FRAME_SIZE as u32 + 1).to_be_bytes();
+        assert!(read_frame(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_json_response() {
+        let encoded = Encoding::Json.encode_response(DaemonResponse::Json(r#"{"a":1}"#.to_string())).unwrap();
+        assert_eq!(String::from_utf8(encoded.clone()).unwrap(), r#"{"status":"json","value":{"a":1}}"#);
+        assert_eq!(Encoding::Json.decode_response(&encoded).unwrap(), DaemonResponse::Json(r#"{"a":1}"#.to_string()));
+    }
+}
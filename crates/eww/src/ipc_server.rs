@@ -1,4 +1,9 @@
-use crate::{app, opts};
+use crate::{
+    app,
+    daemon_response::{DaemonResponse, DaemonResponseReceiver},
+    ipc_protocol::{self, Encoding, Handshake, HandshakeResponse},
+    opts,
+};
 use anyhow::{Context, Result};
 use std::time::Duration;
 use tokio::{
@@ -29,36 +34,111 @@ pub async fn run_server<P: AsRef<std::path::Path>>(evt_send: UnboundedSender<app
 async fn handle_connection(mut stream: tokio::net::UnixStream, evt_send: UnboundedSender<app::DaemonCommand>) -> Result<()> {
     let (mut stream_read, mut stream_write) = stream.split();
 
-    let action: opts::ActionWithServer = read_action_from_stream(&mut stream_read).await?;
+    let encoding = match do_handshake(&mut stream_read, &mut stream_write).await? {
+        Some(encoding) => encoding,
+        None => return Ok(()),
+    };
+
+    let request =
+        ipc_protocol::read_frame_async(&mut stream_read).await?.context("Client disconnected before sending a request")?;
+    let ipc_protocol::Request { action, timeout_ms } = encoding.decode_request(&request)?;
 
     log::debug!("received command from IPC: {:?}", &action);
 
+    let is_subscription = matches!(action, opts::ActionWithServer::Subscribe { .. });
     let (command, maybe_response_recv) = action.into_daemon_command();
 
     evt_send.send(command)?;
 
-    if let Some(mut response_recv) = maybe_response_recv {
-        log::debug!("Waiting for response for IPC client");
-        if let Ok(Some(response)) = tokio::time::timeout(Duration::from_millis(100), response_recv.recv()).await {
-            let response = bincode::serialize(&response)?;
+    match maybe_response_recv {
+        Some(response_recv) if is_subscription => {
+            log::debug!("Streaming responses to IPC client");
+            // The stream is closed once it's dropped, and the client may already be gone, so there is no need to shut it down.
+            return stream_responses(&mut stream_read, &mut stream_write, encoding, response_recv).await;
+        }
+        Some(mut response_recv) => {
+            log::debug!("Waiting for response for IPC client");
+            let response = match timeout_ms {
+                Some(timeout_ms) => match tokio::time::timeout(Duration::from_millis(timeout_ms), response_recv.recv()).await {
+                    Ok(response) => response,
+                    Err(_) => Some(DaemonResponse::Failure(format!(
+                        "Timed out after {}ms while waiting for the daemon to finish the command",
+                        timeout_ms
+                    ))),
+                },
+                None => response_recv.recv().await,
+            };
+            // The response sender is only dropped without a response if handling the command failed.
+            let response = response.unwrap_or_else(|| {
+                DaemonResponse::Failure("The daemon failed to handle the command. Check `eww logs` for details.".to_string())
+            });
+            let response = ipc_protocol::frame(&encoding.encode_response(response)?);
             let result = &stream_write.write_all(&response).await;
             crate::print_result_err!("sending text response to ipc client", &result);
         }
+        None => {}
     }
     stream_write.shutdown().await?;
     Ok(())
 }
 
-/// Read a single message from a unix stream, and parses it into a `ActionWithServer`
-/// The format here requires the first 4 bytes to be the size of the rest of the message (in big-endian), followed by the rest of the message.
-async fn read_action_from_stream(stream_read: &'_ mut tokio::net::unix::ReadHalf<'_>) -> Result<opts::ActionWithServer> {
-    let mut message_byte_length = [0u8; 4];
-    stream_read.read_exact(&mut message_byte_length).await.context("Failed to read message size header in IPC message")?;
-    let message_byte_length = u32::from_be_bytes(message_byte_length);
-    let mut raw_message = Vec::<u8>::with_capacity(message_byte_length as usize);
-    while raw_message.len() < message_byte_length as usize {
-        stream_read.read_buf(&mut raw_message).await.context("Failed to read actual IPC message")?;
-    }
+/// Read the clients [`Handshake`] and answer it.
+/// Returns the encoding the client chose, or `None` if the client was rejected.
+async fn do_handshake(
+    stream_read: &mut tokio::net::unix::ReadHalf<'_>,
+    stream_write: &mut tokio::net::unix::WriteHalf<'_>,
+) -> Result<Option<Encoding>> {
+    let message = ipc_protocol::read_frame_async(stream_read).await?.context("Client disconnected before sending a handshake")?;
+    let handshake: Handshake = match serde_json::from_slice(&message) {
+        Ok(handshake) => handshake,
+        Err(_) => {
+            // Clients from before the versioned protocol directly send a bincode encoded action,
+            // and read a bincode encoded response until the connection is closed.
+            log::warn!("Received a message from an outdated eww client");
+            let response = DaemonResponse::Failure(format!(
+                "The eww daemon is running version {}, which is newer than this client. Restart the daemon (`eww kill`) with \
+                 the same version of eww as the client.",
+                ipc_protocol::EWW_VERSION
+            ));
+            stream_write.write_all(&bincode::serialize(&response)?).await?;
+            return Ok(None);
+        }
+    };
+
+    let response = handshake.respond();
+    stream_write.write_all(&ipc_protocol::frame(&serde_json::to_vec(&response)?)).await?;
+    Ok(match response {
+        HandshakeResponse::Accepted { .. } => Some(handshake.encoding),
+        HandshakeResponse::Rejected { reason, .. } => {
+            log::warn!("Rejected IPC client: {}", reason);
+            None
+        }
+    })
+}
 
-    bincode::deserialize(&raw_message).context("Failed to parse client message")
+/// Forward every response to the client, until either the client disconnects or the response sender is dropped.
+async fn stream_responses(
+    stream_read: &mut tokio::net::unix::ReadHalf<'_>,
+    stream_write: &mut tokio::net::unix::WriteHalf<'_>,
+    encoding: Encoding,
+    mut response_recv: DaemonResponseReceiver,
+) -> Result<()> {
+    // The client doesn't send anything after the initial action, so reading only ever returns once it disconnects.
+    let mut read_buf = [0u8; 64];
+    loop {
+        tokio::select! {
+            response = response_recv.recv() => match response {
+                Some(response) => {
+                    let message = ipc_protocol::frame(&encoding.encode_response(response)?);
+                    if stream_write.write_all(&message).await.is_err() {
+                        log::debug!("IPC client disconnected while streaming responses");
+                        break;
+                    }
+                }
+                None => break,
+            },
+            _ = stream_read.read(&mut read_buf) => break,
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,387 @@
+//! Logging of eww.
+//!
+//! Until the daemon detaches from the terminal, everything is logged to stderr in a human readable format.
+//! The detached daemon instead writes one json encoded [`LogRecord`] per line to its log file,
+//! which is rotated once it gets too large. `eww logs` reads these records back, filtering and formatting them.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use eww_shared_util::VarName;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Once the log file grows beyond this size, it is moved to `<log file>.1` and a new one is started.
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Number of records `eww logs` shows initially when no `--since` is given.
+const INITIAL_RECORD_COUNT: usize = 10;
+
+static LOG_FILE: Lazy<Mutex<Option<LogFile>>> = Lazy::new(|| Mutex::new(None));
+static LOGGING_TO_FILE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+/// A single line of the log file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Time of the record, in milliseconds since the unix epoch
+    pub time: u64,
+    pub level: String,
+    pub module: String,
+    pub message: String,
+    /// Instance id of the window the record concerns, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    /// Name of the variable the record concerns, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub var: Option<String>,
+}
+
+/// The window and variable that log records created on the current thread concern.
+#[derive(Debug, Clone, Default)]
+struct LogContext {
+    window: Option<String>,
+    var: Option<String>,
+}
+
+/// Restores the previous log context of the current thread when dropped.
+#[must_use]
+pub struct ContextGuard(LogContext);
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.0);
+        CONTEXT.with(|context| *context.borrow_mut() = previous);
+    }
+}
+
+fn set_context(f: impl FnOnce(&mut LogContext)) -> ContextGuard {
+    CONTEXT.with(|context| {
+        let previous = context.borrow().clone();
+        f(&mut context.borrow_mut());
+        ContextGuard(previous)
+    })
+}
+
+/// Mark all records logged on the current thread as concerning the given window, until the guard is dropped.
+pub fn window_context(instance_id: &str) -> ContextGuard {
+    set_context(|context| context.window = Some(instance_id.to_string()))
+}
+
+/// Mark all records logged on the current thread as concerning the given variable, until the guard is dropped.
+pub fn var_context(name: &VarName) -> ContextGuard {
+    set_context(|context| context.var = Some(name.to_string()))
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    /// Whether stdout and stderr were redirected to the log file, and thus need to follow it when it is rotated
+    redirect_stdout: bool,
+    redirect_stderr: bool,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> Result<File> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Error opening log file ({}) for writing", path.display()))
+    }
+
+    fn redirect_std(&self) -> Result<()> {
+        let fd = self.file.as_raw_fd();
+        if self.redirect_stdout {
+            nix::unistd::dup2(fd, std::io::stdout().as_raw_fd())?;
+        }
+        if self.redirect_stderr {
+            nix::unistd::dup2(fd, std::io::stderr().as_raw_fd())?;
+        }
+        Ok(())
+    }
+
+    fn rotate_if_too_large(&mut self) -> Result<()> {
+        if self.file.metadata()?.len() < MAX_LOG_FILE_SIZE {
+            return Ok(());
+        }
+        std::fs::rename(&self.path, rotated_log_file(&self.path))?;
+        self.file = Self::open(&self.path)?;
+        self.redirect_std()
+    }
+
+    fn write_record(&mut self, record: &LogRecord) -> Result<()> {
+        self.rotate_if_too_large()?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn rotated_log_file(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Logs to stderr, until [`log_to_file`] is called.
+struct EwwLogger {
+    stderr_logger: pretty_env_logger::env_logger::Logger,
+}
+
+impl log::Log for EwwLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.stderr_logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if !is_logging_to_file() {
+            return self.stderr_logger.log(record);
+        }
+        let context = CONTEXT.with(|context| context.borrow().clone());
+        let record = LogRecord {
+            time: unix_millis(SystemTime::now()),
+            level: record.level().to_string(),
+            module: record.target().to_string(),
+            message: record.args().to_string(),
+            window: context.window,
+            var: context.var,
+        };
+        if let Ok(mut log_file) = LOG_FILE.lock() {
+            if let Some(log_file) = log_file.as_mut() {
+                if let Err(err) = log_file.write_record(&record) {
+                    eprintln!("Failed to write to log file: {:?}", err);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Initialize logging to stderr. Only messages from eww itself are shown, unless `RUST_LOG` is set.
+pub fn init(log_debug: bool) {
+    let mut builder = pretty_env_logger::formatted_timed_builder();
+    match std::env::var("RUST_LOG") {
+        Ok(filters) => builder.parse_filters(&filters),
+        Err(_) => builder.filter(Some("eww"), if log_debug { log::LevelFilter::Debug } else { log::LevelFilter::Info }),
+    };
+    let stderr_logger = builder.build();
+    log::set_max_level(stderr_logger.filter());
+    if let Err(err) = log::set_boxed_logger(Box::new(EwwLogger { stderr_logger })) {
+        eprintln!("Failed to initialize logger: {}", err);
+    }
+}
+
+/// Write all further log records to the given file, also redirecting stdout and stderr there if they are a terminal.
+pub fn log_to_file(path: &Path) -> Result<()> {
+    let log_file = LogFile {
+        path: path.to_path_buf(),
+        file: LogFile::open(path)?,
+        redirect_stdout: nix::unistd::isatty(1)?,
+        redirect_stderr: nix::unistd::isatty(2)?,
+    };
+    log_file.redirect_std()?;
+    *LOG_FILE.lock().unwrap() = Some(log_file);
+    LOGGING_TO_FILE.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Whether log records are written to the log file rather than to stderr.
+pub fn is_logging_to_file() -> bool {
+    LOGGING_TO_FILE.load(Ordering::SeqCst)
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|x| x.as_millis() as u64).unwrap_or_default()
+}
+
+/// A line of the log file. Anything that isn't a [`LogRecord`], like output of GTK, is kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LogLine {
+    Record(LogRecord),
+    Raw(String),
+}
+
+impl LogLine {
+    fn parse(line: String) -> Self {
+        match serde_json::from_str(&line) {
+            Ok(record) => LogLine::Record(record),
+            Err(_) => LogLine::Raw(line),
+        }
+    }
+}
+
+/// Decides which lines `eww logs` shows.
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub min_level: Option<log::Level>,
+    /// Time in milliseconds since the unix epoch
+    pub since: Option<u64>,
+    pub grep: Option<regex::Regex>,
+    pub var: Option<String>,
+}
+
+impl LogFilter {
+    pub fn new(level: Option<&str>, since: Option<Duration>, grep: Option<&str>, var: Option<String>) -> Result<Self> {
+        Ok(LogFilter {
+            min_level: level.map(|level| level.parse()).transpose().context("Invalid log level")?,
+            since: since.map(|since| SystemTime::now().checked_sub(since).map(unix_millis).unwrap_or_default()),
+            grep: grep.map(regex::Regex::new).transpose().context("Invalid regex given to --grep")?,
+            var,
+        })
+    }
+
+    fn matches(&self, line: &LogLine) -> bool {
+        match line {
+            LogLine::Record(record) => {
+                let level_matches = match (&self.min_level, record.level.parse::<log::Level>()) {
+                    (Some(min_level), Ok(level)) => level <= *min_level,
+                    _ => true,
+                };
+                level_matches
+                    && self.since.map_or(true, |since| record.time >= since)
+                    && self.grep.as_ref().map_or(true, |grep| grep.is_match(&record.message))
+                    && self.var.as_ref().map_or(true, |var| record.var.as_ref() == Some(var))
+            }
+            // raw lines can only be filtered by their content
+            LogLine::Raw(line) => {
+                self.min_level.is_none()
+                    && self.since.is_none()
+                    && self.var.is_none()
+                    && self.grep.as_ref().map_or(true, |grep| grep.is_match(line))
+            }
+        }
+    }
+}
+
+fn format_line(line: &LogLine) -> String {
+    match line {
+        LogLine::Raw(line) => line.clone(),
+        LogLine::Record(record) => {
+            let context = [("window", &record.window), ("var", &record.var)]
+                .into_iter()
+                .filter_map(|(key, value)| value.as_ref().map(|value| format!(" [{}={}]", key, value)))
+                .collect::<String>();
+            format!("{} {:<5} {}{} > {}", format_time(record.time), record.level, record.module, context, record.message)
+        }
+    }
+}
+
+/// Format a unix timestamp in milliseconds as local time.
+fn format_time(millis: u64) -> String {
+    let secs = (millis / 1000) as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return millis.to_string();
+    }
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+/// Print the lines of the log file matching the filter, and then keep printing new ones as they get written.
+/// Without a `since` filter, only the last few matching lines are printed initially.
+pub fn follow_log_file(path: &Path, filter: &LogFilter) -> Result<()> {
+    let mut initial_lines = VecDeque::new();
+    let rotated = rotated_log_file(path);
+    for file in [rotated.as_path(), path] {
+        if let Ok(file) = File::open(file) {
+            for line in BufReader::new(file).lines() {
+                let line = LogLine::parse(line?);
+                if filter.matches(&line) {
+                    initial_lines.push_back(line);
+                    if filter.since.is_none() && initial_lines.len() > INITIAL_RECORD_COUNT {
+                        initial_lines.pop_front();
+                    }
+                }
+            }
+        }
+    }
+    for line in &initial_lines {
+        println!("{}", format_line(line));
+    }
+
+    let mut reader = open_at_end(path)?;
+    let mut buffer = String::new();
+    loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? > 0 && buffer.ends_with('\n') {
+            let line = LogLine::parse(buffer.trim_end_matches('\n').to_string());
+            if filter.matches(&line) {
+                println!("{}", format_line(&line));
+            }
+            continue;
+        }
+        // wait for a complete line, starting over when the log file was rotated in the meantime
+        let position = reader.stream_position()? - buffer.len() as u64;
+        std::thread::sleep(Duration::from_millis(200));
+        let current_len = std::fs::metadata(path).map(|x| x.len()).unwrap_or_default();
+        if current_len < position {
+            reader = BufReader::new(File::open(path)?);
+        } else {
+            reader.seek(SeekFrom::Start(position))?;
+        }
+    }
+}
+
+fn open_at_end(path: &Path) -> Result<BufReader<File>> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open log file {}", path.display()))?;
+    file.seek(SeekFrom::End(0))?;
+    Ok(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(time: u64, level: &str, message: &str, var: Option<&str>) -> LogLine {
+        LogLine::Record(LogRecord {
+            time,
+            level: level.to_string(),
+            module: "eww::app".to_string(),
+            message: message.to_string(),
+            window: None,
+            var: var.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_parse_log_line() {
+        let line = LogLine::parse(r#"{"time":1,"level":"INFO","module":"eww::app","message":"hi","var":"foo"}"#.to_string());
+        assert_eq!(line, record(1, "INFO", "hi", Some("foo")));
+        assert_eq!(LogLine::parse("Gtk-WARNING".to_string()), LogLine::Raw("Gtk-WARNING".to_string()));
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let now = unix_millis(SystemTime::now());
+        let filter = LogFilter::new(Some("warn"), None, Some("fail"), Some("foo".to_string())).unwrap();
+        assert!(filter.matches(&record(now, "ERROR", "it failed", Some("foo"))));
+        assert!(!filter.matches(&record(now, "INFO", "it failed", Some("foo"))));
+        assert!(!filter.matches(&record(now, "ERROR", "it worked", Some("foo"))));
+        assert!(!filter.matches(&record(now, "ERROR", "it failed", Some("bar"))));
+        assert!(!filter.matches(&LogLine::Raw("it failed".to_string())));
+
+        let since = LogFilter::new(None, Some(Duration::from_secs(60)), None, None).unwrap();
+        assert!(since.matches(&record(now, "INFO", "recent", None)));
+        assert!(!since.matches(&record(0, "INFO", "old", None)));
+    }
+}
@@ -22,13 +22,17 @@ use crate::server::ForkResult;
 mod app;
 mod application_lifecycle;
 mod client;
+mod completions;
 mod config;
+mod daemon_registry;
 mod daemon_response;
 mod display_backend;
 mod error_handling_ctx;
 mod file_database;
 mod geometry;
+mod ipc_protocol;
 mod ipc_server;
+mod logging;
 mod opts;
 mod paths;
 mod script_var_handler;
@@ -36,17 +40,14 @@ mod server;
 mod state;
 mod util;
 mod widgets;
+mod window_arguments;
+mod window_initiator;
 
 fn main() {
     let eww_binary_name = std::env::args().next().unwrap();
     let opts: opts::Opt = opts::Opt::from_env();
 
-    let log_level_filter = if opts.log_debug { log::LevelFilter::Debug } else { log::LevelFilter::Info };
-    if std::env::var("RUST_LOG").is_ok() {
-        pretty_env_logger::init_timed();
-    } else {
-        pretty_env_logger::formatted_timed_builder().filter(Some("eww"), log_level_filter).init();
-    }
+    logging::init(opts.log_debug);
 
     #[allow(unused)]
     let use_wayland = opts.force_wayland || detect_wayland();
@@ -83,12 +84,18 @@ fn detect_wayland() -> bool {
     session_type.contains("wayland") || (!wayland_display.is_empty() && !session_type.contains("x11"))
 }
 
-fn run<B: DisplayBackend>(opts: opts::Opt, eww_binary_name: String, display_backend: B) -> Result<()> {
-    let paths = opts
-        .config_path
-        .map(EwwPaths::from_config_dir)
-        .unwrap_or_else(EwwPaths::default)
-        .context("Failed to initialize eww paths")?;
+fn run<B: DisplayBackend>(mut opts: opts::Opt, eww_binary_name: String, display_backend: B) -> Result<()> {
+    let paths = match opts.config_path {
+        Some(config_dir) => EwwPaths::from_config_dir(config_dir, opts.instance.clone()),
+        None => EwwPaths::default(opts.instance.clone()),
+    }
+    .context("Failed to initialize eww paths")?;
+
+    if let opts::Action::WithServer(ActionWithServer::Batch { operations }) = &mut opts.action {
+        if operations.is_empty() {
+            *operations = opts::read_batch_operations(std::io::stdin().lock())?;
+        }
+    }
 
     let should_restart = match &opts.action {
         opts::Action::Daemon => opts.restart,
@@ -96,7 +103,7 @@ fn run<B: DisplayBackend>(opts: opts::Opt, eww_binary_name: String, display_back
         opts::Action::ClientOnly(_) => false,
     };
     if should_restart {
-        let response = handle_server_command(&paths, &ActionWithServer::KillServer, 1);
+        let response = handle_server_command(&paths, &ActionWithServer::KillServer, 1, opts.timeout);
         if let Ok(Some(response)) = response {
             handle_daemon_response(response);
         }
@@ -126,22 +133,34 @@ fn run<B: DisplayBackend>(opts: opts::Opt, eww_binary_name: String, display_back
         }
 
         opts::Action::WithServer(ActionWithServer::KillServer) => {
-            if let Some(response) = handle_server_command(&paths, &ActionWithServer::KillServer, 1)? {
+            if let Some(response) = handle_server_command(&paths, &ActionWithServer::KillServer, 1, opts.timeout)? {
                 handle_daemon_response(response);
             }
             false
         }
 
+        opts::Action::WithServer(action @ ActionWithServer::Subscribe { .. }) => {
+            let mut stream = attempt_connect(&paths.get_ipc_socket_file(), 5)?.context("Failed to connect to daemon")?;
+            client::do_server_subscription(&mut stream, &action, |response| match response {
+                // Every update is printed, even if the new value is empty
+                DaemonResponse::Success(x) | DaemonResponse::Json(x) => println!("{}", x),
+                failure => handle_daemon_response(failure),
+            })?;
+            false
+        }
+
         // a running daemon is necessary for this command
         opts::Action::WithServer(action) => {
             // attempt to just send the command to a running daemon
-            match handle_server_command(&paths, &action, 5) {
+            match handle_server_command(&paths, &action, 5, opts.timeout) {
                 Ok(Some(response)) => {
                     handle_daemon_response(response);
                     true
                 }
                 Ok(None) => true,
 
+                // a daemon with a different version is running, so starting another one would not help.
+                Err(err) if err.is::<ipc_protocol::ProtocolMismatch>() => Err(err)?,
                 Err(err) if action.can_start_daemon() && !opts.no_daemonize => {
                     // connecting to the daemon failed. Thus, start the daemon here!
                     log::warn!("Failed to connect to daemon: {}", err);
@@ -156,7 +175,7 @@ fn run<B: DisplayBackend>(opts: opts::Opt, eww_binary_name: String, display_back
                     let fork_result = server::initialize_server(paths.clone(), Some(command), display_backend, true)?;
                     let is_parent = fork_result == ForkResult::Parent;
                     if let (Some(recv), true) = (response_recv, is_parent) {
-                        listen_for_daemon_response(recv);
+                        listen_for_daemon_response(recv, opts.timeout);
                     }
                     is_parent
                 }
@@ -166,35 +185,45 @@ fn run<B: DisplayBackend>(opts: opts::Opt, eww_binary_name: String, display_back
     };
 
     if would_show_logs && opts.show_logs {
-        client::handle_client_only_action(&paths, opts::ActionClientOnly::Logs)?;
+        let show_logs = opts::ActionClientOnly::Logs { level: None, since: None, grep: None, var: None };
+        client::handle_client_only_action(&paths, show_logs)?;
     }
     Ok(())
 }
 
-fn listen_for_daemon_response(mut recv: DaemonResponseReceiver) {
+fn listen_for_daemon_response(mut recv: DaemonResponseReceiver, timeout: Duration) {
     let rt = tokio::runtime::Builder::new_current_thread()
         .thread_name("listen-for-daemon-response")
         .enable_all()
         .build()
         .expect("Failed to initialize tokio runtime");
     rt.block_on(async {
-        if let Ok(Some(response)) = tokio::time::timeout(Duration::from_millis(100), recv.recv()).await {
-            println!("{}", response);
+        match tokio::time::timeout(timeout, recv.recv()).await {
+            Ok(Some(response)) => println!("{}", response),
+            Ok(None) => {}
+            Err(_) => eprintln!("Timed out while waiting for a response from the daemon"),
         }
     })
 }
 
 /// attempt to send a command to the daemon and send it the given action repeatedly.
-fn handle_server_command(paths: &EwwPaths, action: &ActionWithServer, connect_attempts: usize) -> Result<Option<DaemonResponse>> {
+fn handle_server_command(
+    paths: &EwwPaths,
+    action: &ActionWithServer,
+    connect_attempts: usize,
+    timeout: Duration,
+) -> Result<Option<DaemonResponse>> {
     log::debug!("Trying to find server process at socket {}", paths.get_ipc_socket_file().display());
-    let mut stream = attempt_connect(&paths.get_ipc_socket_file(), connect_attempts).context("Failed to connect to daemon")?;
+    let mut stream = attempt_connect(&paths.get_ipc_socket_file(), connect_attempts)?.context("Failed to connect to daemon")?;
     log::debug!("Connected to Eww server ({}).", &paths.get_ipc_socket_file().display());
-    client::do_server_call(&mut stream, action).context("Error while forwarding command to server")
+    client::do_server_call(&mut stream, action, timeout).context("Error while forwarding command to server")
 }
 
 fn handle_daemon_response(res: DaemonResponse) {
     match res {
-        DaemonResponse::Success(x) => println!("{}", x),
+        // Commands that finished without any output
+        DaemonResponse::Success(x) if x.is_empty() => {}
+        DaemonResponse::Success(x) | DaemonResponse::Json(x) => println!("{}", x),
         DaemonResponse::Failure(x) => {
             eprintln!("{}", x);
             std::process::exit(1);
@@ -202,22 +231,31 @@ fn handle_daemon_response(res: DaemonResponse) {
     }
 }
 
-fn attempt_connect(socket_path: impl AsRef<Path>, attempts: usize) -> Option<net::UnixStream> {
+/// Pings are answered by the IPC server directly, so they don't need to wait for the daemon to finish any commands.
+const PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Try to connect to the daemon, making sure it responds to a ping.
+/// Fails with a [`ipc_protocol::ProtocolMismatch`] if a daemon is running that can't talk to this client.
+fn attempt_connect(socket_path: impl AsRef<Path>, attempts: usize) -> Result<Option<net::UnixStream>> {
     for _ in 0..attempts {
         if let Ok(mut con) = net::UnixStream::connect(&socket_path) {
-            if client::do_server_call(&mut con, &opts::ActionWithServer::Ping).is_ok() {
-                return net::UnixStream::connect(&socket_path).ok();
+            match client::do_server_call(&mut con, &opts::ActionWithServer::Ping, PING_TIMEOUT) {
+                Ok(_) => return Ok(net::UnixStream::connect(&socket_path).ok()),
+                Err(err) if err.is::<ipc_protocol::ProtocolMismatch>() => return Err(err),
+                Err(_) => {}
             }
         }
         std::thread::sleep(Duration::from_millis(200));
     }
-    None
+    Ok(None)
 }
 
 /// Check if a eww server is currently running by trying to send a ping message to it.
+/// A daemon of a different eww version counts as running.
 fn check_server_running(socket_path: impl AsRef<Path>) -> bool {
-    let response = net::UnixStream::connect(socket_path)
-        .ok()
-        .and_then(|mut stream| client::do_server_call(&mut stream, &opts::ActionWithServer::Ping).ok());
-    response.is_some()
+    let response = net::UnixStream::connect(socket_path).ok().map(|mut stream| {
+        client::do_server_call(&mut stream, &opts::ActionWithServer::Ping, PING_TIMEOUT)
+            .map_or_else(|err| err.is::<ipc_protocol::ProtocolMismatch>(), |_| true)
+    });
+    response.unwrap_or(false)
 }
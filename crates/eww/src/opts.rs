@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
 use eww_shared_util::VarName;
 use serde::{Deserialize, Serialize};
 use simplexpr::dynval::DynVal;
@@ -10,7 +10,9 @@ use yuck::{
 
 use crate::{
     app,
+    completions::{CompletionShell, NameKind},
     daemon_response::{self, DaemonResponse, DaemonResponseSender},
+    window_arguments::WindowArguments,
 };
 
 /// Struct that gets generated from `RawOpt`.
@@ -21,8 +23,10 @@ pub struct Opt {
     pub show_logs: bool,
     pub restart: bool,
     pub config_path: Option<std::path::PathBuf>,
+    pub instance: Option<String>,
     pub action: Action,
     pub no_daemonize: bool,
+    pub timeout: std::time::Duration,
 }
 
 #[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
@@ -41,6 +45,10 @@ struct RawOpt {
     #[arg(short, long, global = true)]
     config: Option<std::path::PathBuf>,
 
+    /// Name of the daemon to use. This allows running multiple daemons at once, which can be listed with `eww list-daemons`.
+    #[arg(long, global = true)]
+    instance: Option<String>,
+
     /// Watch the log output after executing the command
     #[arg(long = "logs", global = true)]
     show_logs: bool,
@@ -53,6 +61,14 @@ struct RawOpt {
     #[arg(long = "restart", global = true)]
     restart: bool,
 
+    /// Print the output of commands that query the daemon (state, get, windows, debug, status, graph, subscribe) and list-daemons as json
+    #[arg(long = "json", global = true)]
+    json: bool,
+
+    /// How long to wait for the daemon to finish a command (i.e.: 5s, 500ms)
+    #[arg(long, global = true, default_value = "5s", value_parser = parse_duration_arg)]
+    timeout: std::time::Duration,
+
     #[command(subcommand)]
     action: Action,
 }
@@ -74,33 +90,99 @@ pub enum Action {
 pub enum ActionClientOnly {
     /// Print and watch the eww logs
     #[command(name = "logs")]
-    Logs,
+    Logs {
+        /// Only show records of at least this level
+        #[arg(long, value_parser = ["error", "warn", "info", "debug", "trace"])]
+        level: Option<String>,
+
+        /// Only show records newer than this (i.e.: 10m, 1h)
+        #[arg(long, value_parser = parse_duration_arg)]
+        since: Option<std::time::Duration>,
+
+        /// Only show records whose message matches this regex
+        #[arg(long)]
+        grep: Option<String>,
+
+        /// Only show records concerning the given variable
+        #[arg(long)]
+        var: Option<String>,
+    },
+
+    /// List all running eww daemons
+    #[command(name = "list-daemons")]
+    ListDaemons {
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
+
+    /// Check the configuration for errors, without starting the daemon.
+    /// Prints all problems that were found, and exits with a non-zero status if there were any.
+    #[command(name = "check")]
+    Check,
+
+    /// Print a completion script for the given shell, which also completes window and variable names
+    #[command(name = "shell-completions")]
+    ShellCompletions {
+        #[arg(short, long)]
+        shell: CompletionShell,
+    },
+
+    /// Print the names of all windows or variables, used by the shell completions
+    #[command(name = "complete-names", hide = true)]
+    CompleteNames { kind: NameKind },
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize, PartialEq)]
 pub enum ActionWithServer {
     /// Ping the eww server, checking if it is reachable.
     #[clap(name = "ping")]
+    #[serde(rename = "ping")]
     Ping,
 
     /// Update the value of a variable, in a running eww instance
     #[clap(name = "update", alias = "u")]
+    #[serde(rename = "update")]
     Update {
         /// variable_name="new_value"-pairs that will be updated
         #[arg(value_parser = parse_var_update_arg)]
+        #[serde(with = "var_update_mappings")]
         mappings: Vec<(VarName, DynVal)>,
+
+        /// Only change a part of a variable containing json, given as a path into the value, i.e.: `settings.volume=40`.
+        /// Values that are valid json are inserted as such, anything else is inserted as a string.
+        #[arg(long, conflicts_with = "merge")]
+        #[serde(default)]
+        patch: bool,
+
+        /// Apply the given values as json merge patches (RFC 7386) to the current values of the variables.
+        #[arg(long)]
+        #[serde(default)]
+        merge: bool,
     },
 
     /// Open the GTK debugger
     #[command(name = "inspector", alias = "debugger")]
+    #[serde(rename = "inspector")]
     OpenInspector,
 
     /// Open a window
     #[clap(name = "open", alias = "o")]
+    #[serde(rename = "open")]
     OpenWindow {
         /// Name of the window you want to open.
         window_name: String,
 
+        /// Id of this instance of the window, which allows opening the same window multiple times.
+        /// Defaults to the name of the window.
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Values that are available as variables within the window, given as name=value pairs (i.e.: `--arg monitor=0`)
+        #[arg(long = "arg", value_parser = parse_var_update_arg)]
+        #[serde(default, with = "var_update_mappings")]
+        args: Vec<(VarName, DynVal)>,
+
         /// The identifier of the monitor the window should open on
         #[arg(long)]
         screen: Option<MonitorIdentifier>,
@@ -119,62 +201,223 @@ pub enum ActionWithServer {
 
         /// If the window is already open, close it instead
         #[arg(long = "toggle")]
+        #[serde(default)]
         should_toggle: bool,
     },
 
     /// Open multiple windows at once.
     /// NOTE: This will in the future be part of eww open, and will then be removed.
     #[command(name = "open-many")]
+    #[serde(rename = "open-many")]
     OpenMany {
         windows: Vec<String>,
 
         /// If a window is already open, close it instead
         #[arg(long = "toggle")]
+        #[serde(default)]
         should_toggle: bool,
     },
 
-    /// Close the given windows
+    /// Close the given windows, given by their instance id
     #[command(name = "close", alias = "c")]
+    #[serde(rename = "close")]
     CloseWindows { windows: Vec<String> },
 
     /// Reload the configuration
     #[command(name = "reload", alias = "r")]
-    Reload,
+    #[serde(rename = "reload")]
+    Reload {
+        /// Only reload the stylesheet, keeping all windows and variables as they are
+        #[arg(long)]
+        #[serde(default)]
+        css_only: bool,
+    },
 
     /// Kill the eww daemon
     #[command(name = "kill", alias = "k")]
+    #[serde(rename = "kill")]
     KillServer,
 
     /// Close all windows, without killing the daemon
     #[command(name = "close-all", alias = "ca")]
+    #[serde(rename = "close-all")]
     CloseAll,
 
     /// Prints the variables used in all currently open window
     #[command(name = "state")]
+    #[serde(rename = "state")]
     ShowState {
         /// Shows all variables, including not currently used ones
         #[arg(short, long)]
+        #[serde(default)]
         all: bool,
+
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
     },
 
     /// Get the value of a variable if defined
     #[command(name = "get")]
-    GetVar { name: String },
+    #[serde(rename = "get")]
+    GetVar {
+        name: String,
+
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
 
     /// Print the names of all configured windows. Windows with a * in front of them are currently opened.
+    /// Instances opened with an id other than the window name are listed as `id (window name)`.
     #[command(name = "windows")]
-    ShowWindows,
+    #[serde(rename = "windows")]
+    ShowWindows {
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
 
     /// Print out the widget structure as seen by eww.
     ///
     /// This may be useful if you are facing issues with how eww is interpreting your configuration,
     /// and to provide additional context to the eww developers if you are filing a bug.
     #[command(name = "debug")]
-    ShowDebug,
+    #[serde(rename = "debug")]
+    ShowDebug {
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
+
+    /// Show the state of the daemon, including which script-vars are running, when they last updated and their errors.
+    #[command(name = "status")]
+    #[serde(rename = "status")]
+    ShowStatus {
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
 
     /// Print out the scope graph structure in graphviz dot format.
     #[command(name = "graph")]
-    ShowGraph,
+    #[serde(rename = "graph")]
+    ShowGraph {
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
+
+    /// Run multiple operations at once.
+    ///
+    /// All variable updates are applied before any widgets are updated, followed by the window operations.
+    /// Each operation is given like the corresponding eww command, i.e.: `eww batch "update foo=1 bar=2" "open baz"`.
+    /// If no operations are given, they are read from stdin, one per line.
+    #[command(name = "batch")]
+    #[serde(rename = "batch")]
+    Batch {
+        /// The operations to run. Supported are update, open, open-many, close and close-all.
+        #[arg(value_parser = parse_batch_operation)]
+        operations: Vec<BatchOperation>,
+    },
+
+    /// Change the value of a variable based on its current value
+    #[command(name = "var")]
+    #[serde(rename = "var")]
+    Var {
+        #[command(subcommand)]
+        operation: VarOperation,
+    },
+
+    /// Print every update of the given variables, one per line, until interrupted.
+    #[command(name = "subscribe")]
+    #[serde(rename = "subscribe")]
+    Subscribe {
+        #[arg(required = true)]
+        vars: Vec<VarName>,
+
+        #[arg(skip)]
+        #[serde(default)]
+        json: bool,
+    },
+}
+
+/// An operation on the current value of a variable, run by `eww var`.
+/// These are done by the daemon, so they can't be interrupted by other updates of the variable.
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum VarOperation {
+    /// Invert the value of a boolean variable, and print the new value
+    #[command(name = "toggle")]
+    #[serde(rename = "toggle")]
+    Toggle { name: VarName },
+
+    /// Add a number to the value of a numeric variable, and print the new value
+    #[command(name = "incr")]
+    #[serde(rename = "incr")]
+    Incr {
+        name: VarName,
+
+        /// The amount to add. May be negative.
+        #[arg(default_value = "1", allow_negative_numbers = true)]
+        amount: f64,
+    },
+
+    /// Append a value to a variable containing a json array, and print the new length of the array
+    #[command(name = "push")]
+    #[serde(rename = "push")]
+    Push {
+        name: VarName,
+
+        /// The value to append. Values that are valid json are inserted as such, anything else is inserted as a string.
+        value: String,
+
+        /// Remove the oldest elements when the array gets longer than this
+        #[arg(long)]
+        max_length: Option<usize>,
+    },
+
+    /// Remove the last value of a variable containing a json array, and print it
+    #[command(name = "pop")]
+    #[serde(rename = "pop")]
+    Pop { name: VarName },
+}
+
+/// A single operation of `eww batch`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum BatchOperation {
+    #[serde(rename = "update", with = "var_update_mappings")]
+    UpdateVars(Vec<(VarName, DynVal)>),
+    #[serde(rename = "open")]
+    OpenWindow {
+        window_name: String,
+        id: Option<String>,
+        #[serde(default, with = "var_update_mappings")]
+        args: Vec<(VarName, DynVal)>,
+        screen: Option<MonitorIdentifier>,
+        pos: Option<Coords>,
+        size: Option<Coords>,
+        anchor: Option<AnchorPoint>,
+        #[serde(default)]
+        should_toggle: bool,
+    },
+    #[serde(rename = "open-many")]
+    OpenMany {
+        windows: Vec<String>,
+        #[serde(default)]
+        should_toggle: bool,
+    },
+    #[serde(rename = "close")]
+    CloseWindows { windows: Vec<String> },
+    #[serde(rename = "close-all")]
+    CloseAll,
+}
+
+/// Parser for a single operation of `eww batch`, which uses the same syntax as the regular eww commands.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct BatchOperationArgs {
+    #[command(subcommand)]
+    action: ActionWithServer,
 }
 
 impl Opt {
@@ -184,66 +427,191 @@ impl Opt {
     }
 }
 
+/// The clap command of the eww cli, used to generate shell completions.
+pub fn cli_command() -> clap::Command {
+    RawOpt::command()
+}
+
 impl From<RawOpt> for Opt {
     fn from(other: RawOpt) -> Self {
-        let RawOpt { log_debug, force_wayland, config, show_logs, no_daemonize, restart, action } = other;
-        Opt { log_debug, force_wayland, show_logs, restart, config_path: config, action, no_daemonize }
+        let RawOpt { log_debug, force_wayland, config, instance, show_logs, no_daemonize, restart, json, timeout, mut action } =
+            other;
+        match &mut action {
+            Action::WithServer(action) => action.set_json_output(json),
+            Action::ClientOnly(ActionClientOnly::ListDaemons { json: json_output }) => *json_output = json,
+            _ => {}
+        }
+        Opt { log_debug, force_wayland, show_logs, restart, config_path: config, instance, action, no_daemonize, timeout }
     }
 }
 
-fn parse_var_update_arg(s: &str) -> Result<(VarName, DynVal)> {
+pub fn parse_var_update_arg(s: &str) -> Result<(VarName, DynVal)> {
     let (name, value) = s
         .split_once('=')
         .with_context(|| format!("arguments must be in the shape `variable_name=\"new_value\"`, but got: {}", s))?;
     Ok((name.into(), DynVal::from_string(value.to_owned())))
 }
 
+fn parse_duration_arg(s: &str) -> Result<std::time::Duration> {
+    Ok(DynVal::from_string(s.to_owned()).as_duration()?)
+}
+
+fn parse_batch_operation(s: &str) -> Result<BatchOperation> {
+    let args = split_batch_operation(s)?;
+    let action = BatchOperationArgs::try_parse_from(args).map_err(|e| anyhow!("{}", e.render()))?.action;
+    Ok(match action {
+        ActionWithServer::Update { patch: true, .. } | ActionWithServer::Update { merge: true, .. } => {
+            bail!("`{}` can't be used in a batch, as patching variables is not supported in batches", s)
+        }
+        ActionWithServer::Update { mappings, .. } => BatchOperation::UpdateVars(mappings),
+        ActionWithServer::OpenWindow { window_name, id, args, screen, pos, size, anchor, should_toggle } => {
+            BatchOperation::OpenWindow { window_name, id, args, screen, pos, size, anchor, should_toggle }
+        }
+        ActionWithServer::OpenMany { windows, should_toggle } => BatchOperation::OpenMany { windows, should_toggle },
+        ActionWithServer::CloseWindows { windows } => BatchOperation::CloseWindows { windows },
+        ActionWithServer::CloseAll => BatchOperation::CloseAll,
+        _ => bail!("`{}` can't be used in a batch. Supported are update, open, open-many, close and close-all", s),
+    })
+}
+
+/// Split a batch operation into its arguments on whitespace, keeping text in single or double quotes together.
+fn split_batch_operation(s: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => current.get_or_insert_with(String::new).extend(chars.next()),
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        bail!("Unterminated quote in `{}`", s);
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// Read the operations for `eww batch`, one per line. Empty lines and lines starting with `#` are ignored.
+pub fn read_batch_operations(reader: impl std::io::BufRead) -> Result<Vec<BatchOperation>> {
+    reader
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty() && !line.trim_start().starts_with('#')))
+        .map(|line| parse_batch_operation(&line.context("Failed to read batch operations")?))
+        .collect()
+}
+
+/// (De)serialize variable updates as `[name, value]` pairs with plain string values,
+/// so they can easily be written by hand when talking to the daemon using json.
+mod var_update_mappings {
+    use eww_shared_util::VarName;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use simplexpr::dynval::DynVal;
+
+    pub fn serialize<S: Serializer>(mappings: &[(VarName, DynVal)], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(mappings.iter().map(|(name, value)| (name, value.as_str())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(VarName, DynVal)>, D::Error> {
+        let mappings = Vec::<(VarName, String)>::deserialize(deserializer)?;
+        Ok(mappings.into_iter().map(|(name, value)| (name, DynVal::from_string(value))).collect())
+    }
+}
+
 impl ActionWithServer {
     pub fn can_start_daemon(&self) -> bool {
         matches!(self, ActionWithServer::OpenWindow { .. } | ActionWithServer::OpenMany { .. })
     }
 
-    pub fn into_daemon_command(self) -> (app::DaemonCommand, Option<daemon_response::DaemonResponseReceiver>) {
-        let command = match self {
-            ActionWithServer::Update { mappings } => app::DaemonCommand::UpdateVars(mappings),
-            ActionWithServer::OpenInspector => app::DaemonCommand::OpenInspector,
+    /// Request json output from the daemon for the commands that support it.
+    /// This is set from the global `--json` flag, which is why the fields are skipped by clap.
+    fn set_json_output(&mut self, value: bool) {
+        match self {
+            ActionWithServer::ShowState { json, .. }
+            | ActionWithServer::GetVar { json, .. }
+            | ActionWithServer::ShowWindows { json }
+            | ActionWithServer::ShowDebug { json }
+            | ActionWithServer::ShowStatus { json }
+            | ActionWithServer::ShowGraph { json }
+            | ActionWithServer::Subscribe { json, .. } => *json = value,
+            _ => {}
+        }
+    }
 
-            ActionWithServer::KillServer => app::DaemonCommand::KillServer,
-            ActionWithServer::CloseAll => app::DaemonCommand::CloseAll,
+    pub fn into_daemon_command(self) -> (app::DaemonCommand, Option<daemon_response::DaemonResponseReceiver>) {
+        match self {
+            ActionWithServer::Update { mappings, patch: false, merge: false } => {
+                with_response_channel(|sender| app::DaemonCommand::UpdateVars(mappings, Some(sender)))
+            }
+            ActionWithServer::Update { mappings, merge, .. } => {
+                with_response_channel(|sender| app::DaemonCommand::PatchVars { patches: mappings, merge, sender })
+            }
+            ActionWithServer::OpenInspector => with_response_channel(app::DaemonCommand::OpenInspector),
+            ActionWithServer::KillServer => with_response_channel(|sender| app::DaemonCommand::KillServer(Some(sender))),
+            ActionWithServer::CloseAll => with_response_channel(app::DaemonCommand::CloseAll),
             ActionWithServer::Ping => {
                 let (send, recv) = tokio::sync::mpsc::unbounded_channel();
                 let _ = send.send(DaemonResponse::Success("pong".to_owned()));
-                return (app::DaemonCommand::NoOp, Some(recv));
+                (app::DaemonCommand::NoOp, Some(recv))
             }
             ActionWithServer::OpenMany { windows, should_toggle } => {
-                return with_response_channel(|sender| app::DaemonCommand::OpenMany { windows, should_toggle, sender });
+                with_response_channel(|sender| app::DaemonCommand::OpenMany { windows, should_toggle, sender })
             }
-            ActionWithServer::OpenWindow { window_name, pos, size, screen, anchor, should_toggle } => {
-                return with_response_channel(|sender| app::DaemonCommand::OpenWindow {
-                    window_name,
+            ActionWithServer::OpenWindow { window_name, id, args, pos, size, screen, anchor, should_toggle } => {
+                let window_args = WindowArguments {
                     pos,
                     size,
                     anchor,
-                    screen,
-                    should_toggle,
-                    sender,
-                })
+                    monitor: screen,
+                    args: args.into_iter().collect(),
+                    ..WindowArguments::new(window_name).with_instance_id(id)
+                };
+                with_response_channel(|sender| app::DaemonCommand::OpenWindow { window_args, should_toggle, sender })
             }
             ActionWithServer::CloseWindows { windows } => {
-                return with_response_channel(|sender| app::DaemonCommand::CloseWindows { windows, sender });
+                with_response_channel(|sender| app::DaemonCommand::CloseWindows { windows, sender })
+            }
+            ActionWithServer::Reload { css_only: false } => with_response_channel(app::DaemonCommand::ReloadConfigAndCss),
+            ActionWithServer::Reload { css_only: true } => with_response_channel(app::DaemonCommand::ReloadCss),
+            ActionWithServer::ShowWindows { json } => {
+                with_response_channel(|sender| app::DaemonCommand::PrintWindows { json, sender })
+            }
+            ActionWithServer::ShowState { all, json } => {
+                with_response_channel(|sender| app::DaemonCommand::PrintState { all, json, sender })
+            }
+            ActionWithServer::GetVar { name, json } => {
+                with_response_channel(|sender| app::DaemonCommand::GetVar { name, json, sender })
+            }
+            ActionWithServer::ShowDebug { json } => {
+                with_response_channel(|sender| app::DaemonCommand::PrintDebug { json, sender })
+            }
+            ActionWithServer::ShowStatus { json } => {
+                with_response_channel(|sender| app::DaemonCommand::PrintStatus { json, sender })
+            }
+            ActionWithServer::ShowGraph { json } => {
+                with_response_channel(|sender| app::DaemonCommand::PrintGraph { json, sender })
             }
-            ActionWithServer::Reload => return with_response_channel(app::DaemonCommand::ReloadConfigAndCss),
-            ActionWithServer::ShowWindows => return with_response_channel(app::DaemonCommand::PrintWindows),
-            ActionWithServer::ShowState { all } => {
-                return with_response_channel(|sender| app::DaemonCommand::PrintState { all, sender })
+            ActionWithServer::Batch { operations } => {
+                with_response_channel(|sender| app::DaemonCommand::Batch { operations, sender })
             }
-            ActionWithServer::GetVar { name } => {
-                return with_response_channel(|sender| app::DaemonCommand::GetVar { name, sender })
+            ActionWithServer::Var { operation } => with_response_channel(|sender| match operation {
+                VarOperation::Toggle { name } => app::DaemonCommand::ToggleVar { name, sender },
+                VarOperation::Incr { name, amount } => app::DaemonCommand::IncrVar { name, amount, sender },
+                VarOperation::Push { name, value, max_length } => app::DaemonCommand::PushVar { name, value, max_length, sender },
+                VarOperation::Pop { name } => app::DaemonCommand::PopVar { name, sender },
+            }),
+            ActionWithServer::Subscribe { vars, json } => {
+                with_response_channel(|sender| app::DaemonCommand::Subscribe { vars, json, sender })
             }
-            ActionWithServer::ShowDebug => return with_response_channel(app::DaemonCommand::PrintDebug),
-            ActionWithServer::ShowGraph => return with_response_channel(app::DaemonCommand::PrintGraph),
-        };
-        (command, None)
+        }
     }
 }
 
@@ -254,3 +622,56 @@ where
     let (sender, recv) = daemon_response::create_pair();
     (f(sender), Some(recv))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_operation() {
+        assert_eq!(
+            parse_batch_operation(r#"update foo=1 bar="hello world""#).unwrap(),
+            BatchOperation::UpdateVars(vec![("foo".into(), DynVal::from("1")), ("bar".into(), DynVal::from("hello world"))])
+        );
+        assert_eq!(
+            parse_batch_operation("close bar 'other bar'").unwrap(),
+            BatchOperation::CloseWindows { windows: vec!["bar".to_string(), "other bar".to_string()] }
+        );
+        assert_eq!(
+            parse_batch_operation("open bar --id bar-left --arg monitor=0 --arg label='Left bar'").unwrap(),
+            BatchOperation::OpenWindow {
+                window_name: "bar".to_string(),
+                id: Some("bar-left".to_string()),
+                args: vec![("monitor".into(), DynVal::from("0")), ("label".into(), DynVal::from("Left bar"))],
+                screen: None,
+                pos: None,
+                size: None,
+                anchor: None,
+                should_toggle: false
+            }
+        );
+        assert!(parse_batch_operation("kill").is_err());
+        assert!(parse_batch_operation("open 'bar").is_err());
+    }
+
+    #[test]
+    fn test_read_batch_operations() {
+        let input = "# comment\nopen bar --toggle\n\nclose-all\n";
+        assert_eq!(
+            read_batch_operations(input.as_bytes()).unwrap(),
+            vec![
+                BatchOperation::OpenWindow {
+                    window_name: "bar".to_string(),
+                    id: None,
+                    args: Vec::new(),
+                    screen: None,
+                    pos: None,
+                    size: None,
+                    anchor: None,
+                    should_toggle: true
+                },
+                BatchOperation::CloseAll,
+            ]
+        );
+    }
+}
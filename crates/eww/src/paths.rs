@@ -12,10 +12,16 @@ pub struct EwwPaths {
     pub log_file: PathBuf,
     pub ipc_socket_file: PathBuf,
     pub config_dir: PathBuf,
+    /// File the values of persisted variables are stored in
+    pub state_file: PathBuf,
+    /// Name of the daemon given with `--instance`, if any
+    pub instance: Option<String>,
+    /// Identifies the daemon these paths belong to. This is the instance name, or a hash of the config dir for unnamed daemons.
+    pub daemon_id: String,
 }
 
 impl EwwPaths {
-    pub fn from_config_dir<P: AsRef<Path>>(config_dir: P) -> Result<Self> {
+    pub fn from_config_dir<P: AsRef<Path>>(config_dir: P, instance: Option<String>) -> Result<Self> {
         let config_dir = config_dir.as_ref();
         if config_dir.is_file() {
             bail!("Please provide the path to the config directory, not a file within it")
@@ -27,39 +33,61 @@ impl EwwPaths {
 
         let config_dir = config_dir.canonicalize()?;
 
-        let mut hasher = DefaultHasher::new();
-        format!("{}", config_dir.display()).hash(&mut hasher);
-        // daemon_id is a hash of the config dir path to ensure that, given a normal XDG_RUNTIME_DIR,
-        // the absolute path to the socket stays under the 108 bytes limit. (see #387, man 7 unix)
-        let daemon_id = format!("{:x}", hasher.finish());
-
-        let ipc_socket_file = std::env::var("XDG_RUNTIME_DIR")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|_| std::path::PathBuf::from("/tmp"))
-            .join(format!("eww-server_{}", daemon_id));
+        let daemon_id = match &instance {
+            Some(instance) => {
+                if instance.is_empty() || !instance.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    bail!("Invalid instance name `{}`. Only letters, numbers, `-` and `_` are allowed", instance);
+                }
+                instance.clone()
+            }
+            None => {
+                let mut hasher = DefaultHasher::new();
+                format!("{}", config_dir.display()).hash(&mut hasher);
+                // daemon_id is a hash of the config dir path to ensure that, given a normal XDG_RUNTIME_DIR,
+                // the absolute path to the socket stays under the 108 bytes limit. (see #387, man 7 unix)
+                format!("{:x}", hasher.finish())
+            }
+        };
+
+        let ipc_socket_file = runtime_dir().join(format!("eww-server_{}", daemon_id));
 
         // 100 as the limit isn't quite 108 everywhere (i.e 104 on BSD or mac)
         if format!("{}", ipc_socket_file.display()).len() > 100 {
             log::warn!("The IPC socket file's absolute path exceeds 100 bytes, the socket may fail to create.");
         }
 
+        // Unlike the socket, the state file has to be found again after upgrading eww,
+        // so its name can't depend on DefaultHasher, which may change between Rust releases.
+        let state_id = match &instance {
+            Some(instance) => instance.clone(),
+            None => format!("{:x}", stable_hash(&config_dir.display().to_string())),
+        };
+        let state_file = std::env::var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".local/share"))
+            .join("eww")
+            .join(format!("state_{}.json", state_id));
+
         Ok(EwwPaths {
             config_dir,
+            state_file,
             log_file: std::env::var("XDG_CACHE_HOME")
                 .map(PathBuf::from)
                 .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".cache"))
                 .join(format!("eww_{}.log", daemon_id)),
             ipc_socket_file,
+            instance,
+            daemon_id,
         })
     }
 
-    pub fn default() -> Result<Self> {
+    pub fn default(instance: Option<String>) -> Result<Self> {
         let config_dir = std::env::var("XDG_CONFIG_HOME")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".config"))
             .join("eww");
 
-        Self::from_config_dir(config_dir)
+        Self::from_config_dir(config_dir, instance)
     }
 
     pub fn get_log_file(&self) -> &Path {
@@ -70,6 +98,18 @@ impl EwwPaths {
         self.ipc_socket_file.as_path()
     }
 
+    pub fn get_state_file(&self) -> &Path {
+        self.state_file.as_path()
+    }
+
+    pub fn get_instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    pub fn get_daemon_id(&self) -> &str {
+        &self.daemon_id
+    }
+
     pub fn get_config_dir(&self) -> &Path {
         self.config_dir.as_path()
     }
@@ -79,14 +119,35 @@ impl EwwPaths {
     }
 }
 
+/// 64-bit FNV-1a hash, which, unlike [`DefaultHasher`], is guaranteed to stay the same across Rust releases.
+fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Directory the IPC sockets and the registry of running daemons are stored in.
+pub fn runtime_dir() -> PathBuf {
+    std::env::var("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/tmp"))
+}
+
 impl std::fmt::Display for EwwPaths {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "config-dir: {}, ipc-socket: {}, log-file: {}",
+            "config-dir: {}, ipc-socket: {}, log-file: {}, state-file: {}",
             self.config_dir.display(),
             self.ipc_socket_file.display(),
-            self.log_file.display()
+            self.log_file.display(),
+            self.state_file.display()
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(format!("{:x}", stable_hash("/home/user/.config/eww")), "fb283404609fc650");
+    }
+}
@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
 
 use crate::{
     app,
     config::{create_script_var_failed_warn, script_var},
+    logging,
 };
 use anyhow::{anyhow, Result};
 use app::DaemonCommand;
@@ -24,6 +29,8 @@ use yuck::config::script_var_definition::{ListenScriptVar, PollScriptVar, Script
 /// the script var execution.
 pub fn init(evt_send: UnboundedSender<DaemonCommand>) -> ScriptVarHandlerHandle {
     let (msg_send, mut msg_recv) = tokio::sync::mpsc::unbounded_channel();
+    let stats = SharedStats::default();
+    let handler_stats = stats.clone();
     let thread_handle = std::thread::Builder::new()
         .name("outer-script-var-handler".to_string())
         .spawn(move || {
@@ -35,8 +42,8 @@ pub fn init(evt_send: UnboundedSender<DaemonCommand>) -> ScriptVarHandlerHandle
             rt.block_on(async {
                 let _: Result<_> = try {
                     let mut handler = ScriptVarHandler {
-                        listen_handler: ListenVarHandler::new(evt_send.clone())?,
-                        poll_handler: PollVarHandler::new(evt_send)?,
+                        listen_handler: ListenVarHandler::new(evt_send.clone(), handler_stats.clone())?,
+                        poll_handler: PollVarHandler::new(evt_send, handler_stats)?,
                     };
                     crate::loop_select_exiting! {
                         Some(msg) = msg_recv.recv() => match msg {
@@ -57,20 +64,61 @@ pub fn init(evt_send: UnboundedSender<DaemonCommand>) -> ScriptVarHandlerHandle
             })
         })
         .expect("Failed to start script-var-handler thread");
-    ScriptVarHandlerHandle { msg_send, thread_handle }
+    ScriptVarHandlerHandle { msg_send, thread_handle, stats }
+}
+
+/// Runtime statistics of a single script-var, as shown by `eww status`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptVarStats {
+    /// Process id of the command of a listen-var, while it is running
+    pub pid: Option<u32>,
+    /// Whether a poll-var is currently being polled
+    pub polling: bool,
+    /// When the variable last produced a new value
+    pub last_update: Option<SystemTime>,
+    /// The last error that occurred while running the command of the variable
+    pub last_error: Option<String>,
+    /// How often the command of a poll-var was run, or how many values the command of a listen-var produced
+    pub run_count: u64,
+    /// How often the command of a poll-var was run. Always 0 for listen-vars.
+    pub poll_count: u64,
+    /// Total time spent running the command of a poll-var
+    pub total_poll_duration: Duration,
+}
+
+impl ScriptVarStats {
+    /// Whether the command of a listen-var is currently running, or a poll-var is currently being polled.
+    pub fn is_running(&self) -> bool {
+        self.pid.is_some() || self.polling
+    }
+
+    /// Average time it took to run the command of a poll-var, or None for listen-vars and poll-vars that haven't run yet.
+    pub fn average_poll_duration(&self) -> Option<Duration> {
+        (self.poll_count > 0).then(|| self.total_poll_duration.div_f64(self.poll_count as f64))
+    }
+}
+
+/// Statistics of all script-vars that have been started, shared between the handle and the handler thread.
+type SharedStats = Arc<Mutex<HashMap<VarName, ScriptVarStats>>>;
+
+fn update_stats(stats: &SharedStats, name: &VarName, f: impl FnOnce(&mut ScriptVarStats)) {
+    if let Ok(mut stats) = stats.lock() {
+        f(stats.entry(name.clone()).or_default());
+    }
 }
 
 /// Handle to the script-var handling system.
 pub struct ScriptVarHandlerHandle {
     msg_send: UnboundedSender<ScriptVarHandlerMsg>,
     thread_handle: std::thread::JoinHandle<()>,
+    stats: SharedStats,
 }
 
 impl ScriptVarHandlerHandle {
     /// Add a new script-var that should be executed.
     /// This is idempodent, meaning that running a definition that already has a script_var attached which is running
     /// won't do anything.
-    pub fn add(&self, script_var: ScriptVarDefinition) {
+    pub fn add(&mut self, script_var: ScriptVarDefinition) {
         crate::print_result_err!(
             "while forwarding instruction to script-var handler",
             self.msg_send.send(ScriptVarHandlerMsg::AddVar(script_var))
@@ -78,7 +126,7 @@ impl ScriptVarHandlerHandle {
     }
 
     /// Stop the execution of a specific script-var.
-    pub fn stop_for_variable(&self, name: VarName) {
+    pub fn stop_for_variable(&mut self, name: VarName) {
         crate::print_result_err!(
             "while forwarding instruction to script-var handler",
             self.msg_send.send(ScriptVarHandlerMsg::Stop(name)),
@@ -86,13 +134,23 @@ impl ScriptVarHandlerHandle {
     }
 
     /// Stop the execution of all script-vars.
-    pub fn stop_all(&self) {
+    pub fn stop_all(&mut self) {
         crate::print_result_err!(
             "while forwarding instruction to script-var handler",
             self.msg_send.send(ScriptVarHandlerMsg::StopAll)
         );
     }
 
+    /// Get the statistics of the given script-var, which are empty if it has never been started.
+    pub fn stats_of(&self, name: &VarName) -> ScriptVarStats {
+        self.stats.lock().ok().and_then(|stats| stats.get(name).cloned()).unwrap_or_default()
+    }
+
+    /// Get the statistics of all script-vars that have been started at some point, including ones that are stopped by now.
+    pub fn stats(&self) -> HashMap<VarName, ScriptVarStats> {
+        self.stats.lock().map(|stats| stats.clone()).unwrap_or_default()
+    }
+
     pub fn join_thread(self) {
         let _ = self.thread_handle.join();
     }
@@ -140,11 +198,12 @@ impl ScriptVarHandler {
 struct PollVarHandler {
     evt_send: UnboundedSender<DaemonCommand>,
     poll_handles: HashMap<VarName, CancellationToken>,
+    stats: SharedStats,
 }
 
 impl PollVarHandler {
-    fn new(evt_send: UnboundedSender<DaemonCommand>) -> Result<Self> {
-        let handler = PollVarHandler { evt_send, poll_handles: HashMap::new() };
+    fn new(evt_send: UnboundedSender<DaemonCommand>, stats: SharedStats) -> Result<Self> {
+        let handler = PollVarHandler { evt_send, poll_handles: HashMap::new(), stats };
         Ok(handler)
     }
 
@@ -156,26 +215,14 @@ impl PollVarHandler {
         log::debug!("starting poll var {}", &var.name);
         let cancellation_token = CancellationToken::new();
         self.poll_handles.insert(var.name.clone(), cancellation_token.clone());
+        update_stats(&self.stats, &var.name, |stats| stats.polling = true);
         let evt_send = self.evt_send.clone();
+        let stats = self.stats.clone();
         tokio::spawn(async move {
-            let result: Result<_> = try {
-                evt_send.send(app::DaemonCommand::UpdateVars(vec![(var.name.clone(), run_poll_once(&var)?)]))?;
-            };
-            if let Err(err) = result {
-                crate::error_handling_ctx::print_error(err);
-            }
-
+            poll_and_send(&var, &stats, &evt_send);
             crate::loop_select_exiting! {
                 _ = cancellation_token.cancelled() => break,
-                _ = tokio::time::sleep(var.interval) => {
-                    let result: Result<_> = try {
-                        evt_send.send(app::DaemonCommand::UpdateVars(vec![(var.name.clone(), run_poll_once(&var)?)]))?;
-                    };
-
-                    if let Err(err) = result {
-                        crate::error_handling_ctx::print_error(err);
-                    }
-                }
+                _ = tokio::time::sleep(var.interval) => poll_and_send(&var, &stats, &evt_send),
             }
         });
     }
@@ -183,15 +230,47 @@ impl PollVarHandler {
     fn stop_for_variable(&mut self, name: &VarName) {
         if let Some(token) = self.poll_handles.remove(name) {
             log::debug!("stopped poll var {}", name);
+            update_stats(&self.stats, name, |stats| stats.polling = false);
             token.cancel()
         }
     }
 
     fn stop_all(&mut self) {
-        self.poll_handles.drain().for_each(|(_, token)| token.cancel());
+        for (name, token) in self.poll_handles.drain() {
+            update_stats(&self.stats, &name, |stats| stats.polling = false);
+            token.cancel();
+        }
+    }
+}
+
+/// Run the command of a poll-var once and send the resulting value to the app, logging any errors.
+fn poll_and_send(var: &PollScriptVar, stats: &SharedStats, evt_send: &UnboundedSender<DaemonCommand>) {
+    let _log_context = logging::var_context(&var.name);
+    let result: Result<_> = try {
+        let value = run_poll_recording_stats(var, stats)?;
+        evt_send.send(app::DaemonCommand::UpdateVars(vec![(var.name.clone(), value)], None))?;
+    };
+    if let Err(err) = result {
+        crate::error_handling_ctx::print_error(err);
     }
 }
 
+/// Run the command of a poll-var once, recording how long it took and whether it failed.
+fn run_poll_recording_stats(var: &PollScriptVar, stats: &SharedStats) -> Result<DynVal> {
+    let start = Instant::now();
+    let result = run_poll_once(var);
+    update_stats(stats, &var.name, |stats| {
+        stats.run_count += 1;
+        stats.poll_count += 1;
+        stats.total_poll_duration += start.elapsed();
+        match &result {
+            Ok(_) => stats.last_update = Some(SystemTime::now()),
+            Err(err) => stats.last_error = Some(err.to_string()),
+        }
+    });
+    result
+}
+
 fn run_poll_once(var: &PollScriptVar) -> Result<DynVal> {
     match &var.command {
         VarSource::Shell(span, command) => {
@@ -210,11 +289,12 @@ impl Drop for PollVarHandler {
 struct ListenVarHandler {
     evt_send: UnboundedSender<DaemonCommand>,
     listen_process_handles: HashMap<VarName, cancellation::AwaitableCancelationSender>,
+    stats: SharedStats,
 }
 
 impl ListenVarHandler {
-    fn new(evt_send: UnboundedSender<DaemonCommand>) -> Result<Self> {
-        let handler = ListenVarHandler { evt_send, listen_process_handles: HashMap::new() };
+    fn new(evt_send: UnboundedSender<DaemonCommand>, stats: SharedStats) -> Result<Self> {
+        let handler = ListenVarHandler { evt_send, listen_process_handles: HashMap::new(), stats };
         Ok(handler)
     }
 
@@ -228,47 +308,20 @@ impl ListenVarHandler {
             return;
         }
 
-        let (cancel_send, mut cancel_recv) = cancellation::create();
+        let (cancel_send, cancel_recv) = cancellation::create();
         self.listen_process_handles.insert(var.name.clone(), cancel_send);
 
         let evt_send = self.evt_send.clone();
+        let stats = self.stats.clone();
         tokio::spawn(async move {
-            crate::try_logging_errors!(format!("Executing listen var-command {}", &var.command) =>  {
-                let mut handle = unsafe {
-                    tokio::process::Command::new("sh")
-                    .args(&["-c", &var.command])
-                    .stdout(std::process::Stdio::piped())
-                    .stderr(std::process::Stdio::piped())
-                    .stdin(std::process::Stdio::null())
-                    .pre_exec(|| {
-                        let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
-                        Ok(())
-                    }).spawn()?
-                };
-                let mut stdout_lines = BufReader::new(handle.stdout.take().unwrap()).lines();
-                let mut stderr_lines = BufReader::new(handle.stderr.take().unwrap()).lines();
-                let mut completion_notify = None;
-                crate::loop_select_exiting! {
-                    _ = handle.wait() => break,
-                    notify = cancel_recv.wait_for_cancel() => {
-                        completion_notify = notify;
-                        break;
-                    }
-                    Ok(Some(line)) = stdout_lines.next_line() => {
-                        let new_value = DynVal::from_string(line.to_owned());
-                        evt_send.send(DaemonCommand::UpdateVars(vec![(var.name.to_owned(), new_value)]))?;
-                    }
-                    Ok(Some(line)) = stderr_lines.next_line() => {
-                        log::warn!("stderr of `{}`: {}", var.name, line);
-                    }
-                    else => break,
-                };
-                terminate_handle(handle).await;
-
-                if let Some(completion_notify) = completion_notify {
-                    completion_notify.completed().await;
-                }
-            });
+            let result = run_listen_var(&var, &stats, &evt_send, cancel_recv).await;
+            if let Err(err) = &result {
+                update_stats(&stats, &var.name, |stats| {
+                    stats.pid = None;
+                    stats.last_error = Some(err.to_string());
+                });
+            }
+            crate::print_result_err!(format!("while executing listen var-command {}", &var.command), result);
         });
     }
 
@@ -286,6 +339,71 @@ impl ListenVarHandler {
     }
 }
 
+/// Run the command of a listen-var until it exits or is cancelled, sending every line it outputs to the app.
+async fn run_listen_var(
+    var: &ListenScriptVar,
+    stats: &SharedStats,
+    evt_send: &UnboundedSender<DaemonCommand>,
+    mut cancel_recv: cancellation::AwaitableCancelationReceiver,
+) -> Result<()> {
+    let mut handle = unsafe {
+        tokio::process::Command::new("sh")
+            .args(&["-c", &var.command])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .stdin(std::process::Stdio::null())
+            .pre_exec(|| {
+                let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+                Ok(())
+            })
+            .spawn()?
+    };
+    update_stats(stats, &var.name, |stats| stats.pid = handle.id());
+    let mut stdout_lines = BufReader::new(handle.stdout.take().unwrap()).lines();
+    let mut stderr_lines = BufReader::new(handle.stderr.take().unwrap()).lines();
+    let mut completion_notify = None;
+    let mut send_result = Ok(());
+    crate::loop_select_exiting! {
+        status = handle.wait() => {
+            if let Ok(status) = status {
+                if !status.success() {
+                    let error = format!("Command exited with {}", status);
+                    update_stats(stats, &var.name, |stats| stats.last_error = Some(error));
+                }
+            }
+            break;
+        }
+        notify = cancel_recv.wait_for_cancel() => {
+            completion_notify = notify;
+            break;
+        }
+        Ok(Some(line)) = stdout_lines.next_line() => {
+            update_stats(stats, &var.name, |stats| {
+                stats.run_count += 1;
+                stats.last_update = Some(SystemTime::now());
+            });
+            let new_value = DynVal::from_string(line.to_owned());
+            send_result = evt_send.send(DaemonCommand::UpdateVars(vec![(var.name.to_owned(), new_value)], None));
+            if send_result.is_err() {
+                break;
+            }
+        }
+        Ok(Some(line)) = stderr_lines.next_line() => {
+            let _log_context = logging::var_context(&var.name);
+            log::warn!("stderr of `{}`: {}", var.name, line);
+        }
+        else => break,
+    };
+    terminate_handle(handle).await;
+    update_stats(stats, &var.name, |stats| stats.pid = None);
+
+    if let Some(completion_notify) = completion_notify {
+        completion_notify.completed().await;
+    }
+    send_result?;
+    Ok(())
+}
+
 impl Drop for ListenVarHandler {
     fn drop(&mut self) {
         if !self.listen_process_handles.is_empty() {
@@ -355,3 +473,28 @@ mod cancellation {
         (AwaitableCancelationSender(send), AwaitableCancelationReceiver(recv))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_average_poll_duration() {
+        let listen_stats = ScriptVarStats { run_count: 3, ..ScriptVarStats::default() };
+        assert_eq!(listen_stats.average_poll_duration(), None);
+        let poll_stats = ScriptVarStats {
+            run_count: 2,
+            poll_count: 2,
+            total_poll_duration: Duration::from_millis(30),
+            ..ScriptVarStats::default()
+        };
+        assert_eq!(poll_stats.average_poll_duration(), Some(Duration::from_millis(15)));
+    }
+
+    #[test]
+    fn test_is_running() {
+        assert!(!ScriptVarStats { run_count: 3, ..ScriptVarStats::default() }.is_running());
+        assert!(ScriptVarStats { pid: Some(42), ..ScriptVarStats::default() }.is_running());
+        assert!(ScriptVarStats { polling: true, ..ScriptVarStats::default() }.is_running());
+    }
+}
@@ -1,9 +1,9 @@
 use crate::{
     app::{self, DaemonCommand},
-    config, daemon_response,
+    config, daemon_registry, daemon_response,
     display_backend::DisplayBackend,
-    error_handling_ctx, ipc_server, script_var_handler,
-    state::scope_graph::ScopeGraph,
+    error_handling_ctx, ipc_server, logging, script_var_handler,
+    state::{persistence, scope_graph::ScopeGraph},
     EwwPaths,
 };
 use anyhow::{Context, Result};
@@ -11,7 +11,6 @@ use anyhow::{Context, Result};
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
-    os::unix::io::AsRawFd,
     path::Path,
     rc::Rc,
     sync::{atomic::Ordering, Arc},
@@ -57,6 +56,10 @@ pub fn initialize_server<B: DisplayBackend>(
     "#
     );
 
+    if let Err(err) = daemon_registry::register(&paths) {
+        error_handling_ctx::print_error(err);
+    }
+
     simple_signal::set_handler(&[simple_signal::Signal::Int, simple_signal::Signal::Term], move |_| {
         log::info!("Shutting down eww daemon...");
         if let Err(e) = crate::application_lifecycle::send_exit() {
@@ -67,20 +70,26 @@ pub fn initialize_server<B: DisplayBackend>(
 
     gtk::init()?;
 
+    let _ = crate::widgets::APP_EVT_SEND.set(ui_send.clone());
+
     log::debug!("Initializing script var handler");
     let script_var_handler = script_var_handler::init(ui_send.clone());
 
     let (scope_graph_evt_send, mut scope_graph_evt_recv) = tokio::sync::mpsc::unbounded_channel();
 
+    let mut initial_state = eww_config.generate_initial_state()?;
+    let persisted_vars = eww_config.get_persisted_vars();
+    if let Err(err) = persistence::restore_persisted_vars(paths.get_state_file(), persisted_vars, &mut initial_state) {
+        error_handling_ctx::print_error(err);
+    }
+
     let mut app = app::App {
         display_backend,
-        scope_graph: Rc::new(RefCell::new(ScopeGraph::from_global_vars(
-            eww_config.generate_initial_state()?,
-            scope_graph_evt_send,
-        ))),
+        scope_graph: Rc::new(RefCell::new(ScopeGraph::from_global_vars(initial_state, scope_graph_evt_send))),
         eww_config,
         open_windows: HashMap::new(),
         failed_windows: HashSet::new(),
+        instance_id_to_args: HashMap::new(),
         css_provider: gtk::CssProvider::new(),
         script_var_handler,
         app_evt_send: ui_send.clone(),
@@ -99,6 +108,7 @@ pub fn initialize_server<B: DisplayBackend>(
 
     // initialize all the handlers and tasks running asyncronously
     init_async_part(app.paths.clone(), ui_send);
+    let paths = app.paths.clone();
 
     glib::MainContext::default().spawn_local(async move {
         // if an action was given to the daemon initially, execute it first.
@@ -121,6 +131,7 @@ pub fn initialize_server<B: DisplayBackend>(
 
     gtk::main();
     log::info!("main application thread finished");
+    daemon_registry::unregister(&paths);
 
     Ok(ForkResult::Child)
 }
@@ -154,7 +165,7 @@ fn init_async_part(paths: EwwPaths, ui_send: UnboundedSender<app::DaemonCommand>
                         let _ = crate::application_lifecycle::recv_exit().await;
                         log::debug!("Forward task received exit event");
                         // Then forward that to the application
-                        let _ = ui_send.send(app::DaemonCommand::KillServer);
+                        let _ = ui_send.send(app::DaemonCommand::KillServer(None));
                     })
                 };
 
@@ -168,19 +179,37 @@ fn init_async_part(paths: EwwPaths, ui_send: UnboundedSender<app::DaemonCommand>
         .expect("Failed to start outer-main-async-runtime thread");
 }
 
+/// What needs to be reloaded after files in the config directory changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReloadKind {
+    /// The yuck configuration changed, requiring the whole configuration and the stylesheet to be reloaded
+    Full,
+    /// Only the stylesheet changed
+    CssOnly,
+}
+
 /// Watch configuration files for changes, sending reload events to the eww app when the files change.
+/// Changes to only the stylesheet just reload the CSS, leaving windows and variables untouched.
 async fn run_filewatch<P: AsRef<Path>>(config_dir: P, evt_send: UnboundedSender<app::DaemonCommand>) -> Result<()> {
     use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
     let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
         Ok(notify::Event { kind: notify::EventKind::Modify(_), paths, .. }) => {
-            let relevant_files_changed = paths.iter().any(|path| {
+            let yuck_changed = paths.iter().any(|path| path.extension().unwrap_or_default() == "yuck");
+            let css_changed = paths.iter().any(|path| {
                 let ext = path.extension().unwrap_or_default();
-                ext == "yuck" || ext == "scss" || ext == "css"
+                ext == "scss" || ext == "css"
             });
-            if relevant_files_changed {
-                if let Err(err) = tx.send(()) {
+            let reload_kind = if yuck_changed {
+                Some(ReloadKind::Full)
+            } else if css_changed {
+                Some(ReloadKind::CssOnly)
+            } else {
+                None
+            };
+            if let Some(reload_kind) = reload_kind {
+                if let Err(err) = tx.send(reload_kind) {
                     log::warn!("Error forwarding file update event: {:?}", err);
                 }
             }
@@ -190,12 +219,17 @@ async fn run_filewatch<P: AsRef<Path>>(config_dir: P, evt_send: UnboundedSender<
     })?;
     watcher.watch(config_dir.as_ref(), RecursiveMode::Recursive)?;
 
-    // make sure to not trigger reloads too much by only accepting one reload every 500ms.
-    let debounce_done = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    // make sure to not trigger reloads too much by only accepting one reload of each kind every 500ms.
+    // Both kinds are debounced separately, so a change to the yuck configuration is never swallowed by a CSS reload.
+    let debounce_full_done = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let debounce_css_done = Arc::new(std::sync::atomic::AtomicBool::new(true));
 
     crate::loop_select_exiting! {
-        Some(()) = rx.recv() => {
-            let debounce_done = debounce_done.clone();
+        Some(reload_kind) = rx.recv() => {
+            let debounce_done = match reload_kind {
+                ReloadKind::Full => debounce_full_done.clone(),
+                ReloadKind::CssOnly => debounce_css_done.clone(),
+            };
             if debounce_done.swap(false, Ordering::SeqCst) {
                 tokio::spawn(async move {
                     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
@@ -208,10 +242,16 @@ async fn run_filewatch<P: AsRef<Path>>(config_dir: P, evt_send: UnboundedSender<
                 // and eww being too fast, thus reading the file while it's empty.
                 // There should be some cleaner solution for this, but this will do for now.
                 tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-                evt_send.send(app::DaemonCommand::ReloadConfigAndCss(daemon_resp_sender))?;
+                let command = match reload_kind {
+                    ReloadKind::Full => app::DaemonCommand::ReloadConfigAndCss(daemon_resp_sender),
+                    ReloadKind::CssOnly => app::DaemonCommand::ReloadCss(daemon_resp_sender),
+                };
+                evt_send.send(command)?;
                 tokio::spawn(async move {
                     match daemon_resp_response.recv().await {
-                        Some(daemon_response::DaemonResponse::Success(_)) => log::info!("Reloaded config successfully"),
+                        Some(daemon_response::DaemonResponse::Success(_) | daemon_response::DaemonResponse::Json(_)) => {
+                            log::info!("Reloaded config successfully")
+                        }
                         Some(daemon_response::DaemonResponse::Failure(e)) => eprintln!("{}", e),
                         None => log::error!("No response to reload configuration-reload request"),
                     }
@@ -229,7 +269,7 @@ pub enum ForkResult {
     Child,
 }
 
-/// detach the process from the terminal, also redirecting stdout and stderr to LOG_FILE
+/// detach the process from the terminal, also redirecting stdout, stderr and the log to LOG_FILE
 fn do_detach(log_file_path: impl AsRef<Path>) -> Result<ForkResult> {
     // detach from terminal
     match unsafe { nix::unistd::fork()? } {
@@ -245,19 +285,7 @@ fn do_detach(log_file_path: impl AsRef<Path>) -> Result<ForkResult> {
         }
     }
 
-    let file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_file_path)
-        .unwrap_or_else(|_| panic!("Error opening log file ({}), for writing", log_file_path.as_ref().to_string_lossy()));
-    let fd = file.as_raw_fd();
-
-    if nix::unistd::isatty(1)? {
-        nix::unistd::dup2(fd, std::io::stdout().as_raw_fd())?;
-    }
-    if nix::unistd::isatty(2)? {
-        nix::unistd::dup2(fd, std::io::stderr().as_raw_fd())?;
-    }
+    logging::log_to_file(log_file_path.as_ref())?;
 
     Ok(ForkResult::Child)
 }
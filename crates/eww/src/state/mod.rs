@@ -1,4 +1,5 @@
 mod one_to_n_elements_map;
+pub mod persistence;
 pub mod scope;
 pub mod scope_graph;
 
@@ -0,0 +1,70 @@
+//! Saving and restoring the values of variables defined with `:persist true`, so they survive restarts of the daemon.
+
+use anyhow::{Context, Result};
+use eww_shared_util::VarName;
+use simplexpr::dynval::DynVal;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// Read the values stored in the state file. A missing state file is treated as empty.
+pub fn load_persisted_vars(state_file: &Path) -> Result<HashMap<VarName, DynVal>> {
+    if !state_file.exists() {
+        return Ok(HashMap::new());
+    }
+    let content =
+        std::fs::read_to_string(state_file).with_context(|| format!("Failed to read state file {}", state_file.display()))?;
+    let values: HashMap<VarName, String> =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse state file {}", state_file.display()))?;
+    Ok(values.into_iter().map(|(name, value)| (name, DynVal::from_string(value))).collect())
+}
+
+/// Write the values of the given variables to the state file, replacing its previous content.
+pub fn save_persisted_vars<'a>(state_file: &Path, values: impl IntoIterator<Item = (&'a VarName, &'a DynVal)>) -> Result<()> {
+    let values: HashMap<&VarName, &str> = values.into_iter().map(|(name, value)| (name, value.as_str())).collect();
+    if let Some(parent) = state_file.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    // write to a temporary file first, so the state file is never left half-written
+    let tmp_file = state_file.with_extension("json.tmp");
+    std::fs::write(&tmp_file, serde_json::to_string_pretty(&values)?)
+        .with_context(|| format!("Failed to write state file {}", tmp_file.display()))?;
+    std::fs::rename(&tmp_file, state_file).with_context(|| format!("Failed to write state file {}", state_file.display()))?;
+    Ok(())
+}
+
+/// Replace the values of all persisted variables with the values stored in the state file.
+/// Values of variables that are no longer persisted are ignored.
+pub fn restore_persisted_vars(
+    state_file: &Path,
+    persisted_vars: &HashSet<VarName>,
+    vars: &mut HashMap<VarName, DynVal>,
+) -> Result<()> {
+    if persisted_vars.is_empty() {
+        return Ok(());
+    }
+    let stored = load_persisted_vars(state_file)?;
+    vars.extend(stored.into_iter().filter(|(name, _)| persisted_vars.contains(name)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_save_and_restore() {
+        let state_file = std::env::temp_dir().join(format!("eww-test-{}", std::process::id())).join("state.json");
+        let (foo, bar) = (VarName::from("foo"), VarName::from("bar"));
+        let values = maplit::hashmap! { foo.clone() => DynVal::from("saved"), bar.clone() => DynVal::from("other") };
+        save_persisted_vars(&state_file, &values).unwrap();
+
+        let mut vars = maplit::hashmap! { foo.clone() => DynVal::from("initial"), bar.clone() => DynVal::from("initial") };
+        restore_persisted_vars(&state_file, &maplit::hashset! { foo.clone() }, &mut vars).unwrap();
+        assert_eq!(vars.get(&foo), Some(&DynVal::from("saved")));
+        assert_eq!(vars.get(&bar), Some(&DynVal::from("initial")));
+
+        std::fs::remove_dir_all(state_file.parent().unwrap()).unwrap();
+    }
+}
@@ -8,7 +8,7 @@ use eww_shared_util::{AttrName, VarName};
 use simplexpr::{dynval::DynVal, SimplExpr};
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::error_handling_ctx;
+use crate::{daemon_response::DaemonResponseSender, error_handling_ctx};
 
 use super::scope::{Listener, Scope};
 
@@ -28,6 +28,30 @@ impl ScopeIndex {
 
 pub enum ScopeGraphEvent {
     RemoveScope(ScopeIndex),
+    /// Sent when the client of a [`GlobalSubscription`] disconnected.
+    RemoveClosedSubscriptions,
+    /// Re-evaluate everything in the scope that depends on the given variable, even though its value did not change.
+    /// Sent by the timer of a widget with a `:refresh-interval`.
+    NotifyValueChanged(ScopeIndex, VarName),
+}
+
+/// A client that gets notified about every update to a set of global variables, as created by `eww subscribe`.
+#[derive(Debug)]
+pub struct GlobalSubscription {
+    vars: Vec<VarName>,
+    json: bool,
+    sender: DaemonResponseSender,
+}
+
+impl GlobalSubscription {
+    /// Send a value to the subscriber. This fails if the subscriber has disconnected.
+    fn notify(&self, name: &VarName, value: &DynVal) -> Result<()> {
+        if self.json {
+            self.sender.send_json(serde_json::json!({ "name": name.0, "value": value.as_str() }))
+        } else {
+            self.sender.send_success(format!("{}: {}", name, value))
+        }
+    }
 }
 
 /// A graph structure of scopes where each scope may inherit from another scope,
@@ -60,6 +84,10 @@ pub struct ScopeGraph {
     pub root_index: ScopeIndex,
     // TODO this should be factored out, it doesn't really belong into this module / struct.
     pub event_sender: UnboundedSender<ScopeGraphEvent>,
+    /// Subscriptions to global variables. These are kept across [`ScopeGraph::clear`].
+    global_subscriptions: Vec<GlobalSubscription>,
+    /// Listeners that were triggered while inside [`ScopeGraph::with_deferred_listeners`], which will be called once it finishes.
+    deferred_listeners: Option<Vec<(ScopeIndex, Rc<Listener>)>>,
 }
 
 impl ScopeGraph {
@@ -75,11 +103,66 @@ impl ScopeGraph {
         if let Some(scope) = graph.scope_at_mut(root_index) {
             scope.node_index = root_index;
         }
-        Self { graph, root_index, event_sender }
+        Self { graph, root_index, event_sender, global_subscriptions: Vec::new(), deferred_listeners: None }
     }
 
     pub fn update_global_value(&mut self, var_name: &VarName, value: DynVal) -> Result<()> {
-        self.update_value(self.root_index, var_name, value)
+        self.update_value(self.root_index, var_name, value)?;
+        self.notify_subscriptions(var_name);
+        Ok(())
+    }
+
+    /// Send the current value of the given global variable to all subscriptions to it.
+    fn notify_subscriptions(&mut self, var_name: &VarName) {
+        let value = match self.graph.scope_at(self.root_index).and_then(|scope| scope.data.get(var_name)) {
+            Some(value) => value,
+            None => return,
+        };
+        // subscriptions whose client disconnected are dropped here
+        self.global_subscriptions
+            .retain(|subscription| !subscription.vars.contains(var_name) || subscription.notify(var_name, value).is_ok());
+    }
+
+    /// Remove all subscriptions whose client disconnected.
+    pub fn remove_closed_subscriptions(&mut self) {
+        self.global_subscriptions.retain(|subscription| !subscription.sender.is_closed());
+    }
+
+    /// Run the given function, delaying all listener calls it causes until it's done.
+    /// Each listener is then only called once, even if it was triggered by multiple updates.
+    /// This avoids listeners running with partially updated state when updating multiple variables at once.
+    pub fn with_deferred_listeners<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.deferred_listeners.is_some() {
+            return f(self);
+        }
+        self.deferred_listeners = Some(Vec::new());
+        let result = f(self);
+        for (scope_index, listener) in self.deferred_listeners.take().unwrap_or_default() {
+            // the scope might have been removed by one of the listeners called before
+            if self.graph.scope_at(scope_index).is_some() {
+                if let Err(err) = self.call_listener(scope_index, &listener) {
+                    error_handling_ctx::print_error(err);
+                }
+            }
+        }
+        result
+    }
+
+    /// Notify the given sender about every future update to the given global variables, starting with their current values.
+    /// The subscription is removed once the receiving end of the sender is dropped.
+    /// Variables that don't exist in the global scope are ignored.
+    pub fn subscribe_to_globals(&mut self, vars: Vec<VarName>, json: bool, sender: DaemonResponseSender) {
+        self.remove_closed_subscriptions();
+        let subscription = GlobalSubscription { vars, json, sender };
+        let global_data = &self.global_scope().data;
+        let initial_result: Result<()> = subscription
+            .vars
+            .iter()
+            .filter_map(|name| global_data.get(name).map(|value| (name, value)))
+            .try_for_each(|(name, value)| subscription.notify(name, value));
+        if initial_result.is_ok() {
+            self.global_subscriptions.push(subscription);
+        }
     }
 
     pub fn handle_scope_graph_event(&mut self, evt: ScopeGraphEvent) {
@@ -87,10 +170,20 @@ impl ScopeGraph {
             ScopeGraphEvent::RemoveScope(scope_index) => {
                 self.remove_scope(scope_index);
             }
+            ScopeGraphEvent::RemoveClosedSubscriptions => self.remove_closed_subscriptions(),
+            ScopeGraphEvent::NotifyValueChanged(scope_index, var_name) => {
+                // The scope might have been removed since the event was sent
+                if self.scope_at(scope_index).is_some() {
+                    if let Err(err) = self.notify_value_changed(scope_index, &var_name) {
+                        error_handling_ctx::print_error(err);
+                    }
+                }
+            }
         }
     }
 
     /// Fully reinitialize the scope graph. Completely removes all state, and resets the ScopeIndex uniqueness.
+    /// Subscriptions are kept, and notified about the new values.
     pub fn clear(&mut self, vars: HashMap<VarName, DynVal>) {
         self.graph.clear();
         let root_index = self.graph.add_scope(Scope {
@@ -104,6 +197,32 @@ impl ScopeGraph {
             scope.node_index = root_index;
         }
         self.root_index = root_index;
+        let subscribed_vars: HashSet<VarName> =
+            self.global_subscriptions.iter().flat_map(|subscription| subscription.vars.iter().cloned()).collect();
+        for name in subscribed_vars {
+            self.notify_subscriptions(&name);
+        }
+    }
+
+    /// Replace all global variables with the given ones, keeping all other scopes in place.
+    /// Listeners and subscriptions are notified about every variable whose value changed.
+    pub fn replace_globals(&mut self, vars: HashMap<VarName, DynVal>) -> Result<()> {
+        let root_index = self.root_index;
+        let global_data = &mut self.graph.scope_at_mut(root_index).context("No root scope in graph")?.data;
+        global_data.retain(|name, _| vars.contains_key(name));
+        let mut changed_vars = Vec::new();
+        for (name, value) in vars {
+            if global_data.get(&name).map(|x| x.as_str()) != Some(value.as_str()) {
+                changed_vars.push(name.clone());
+            }
+            global_data.insert(name, value);
+        }
+        for name in &changed_vars {
+            self.notify_subscriptions(name);
+        }
+        self.with_deferred_listeners(|scope_graph| {
+            changed_vars.iter().try_for_each(|name| scope_graph.notify_value_changed(root_index, name))
+        })
     }
 
     pub fn remove_scope(&mut self, scope_index: ScopeIndex) {
@@ -118,6 +237,11 @@ impl ScopeGraph {
         self.graph.visualize()
     }
 
+    /// Json representation of the graph, containing the same information as [`Self::visualize`].
+    pub fn visualize_json(&self) -> serde_json::Value {
+        self.graph.visualize_json()
+    }
+
     pub fn currently_used_globals(&self) -> HashSet<VarName> {
         self.variables_used_in_self_or_subscopes_of(self.root_index)
     }
@@ -131,6 +255,15 @@ impl ScopeGraph {
         self.graph.scope_at(index)
     }
 
+    pub fn scope_count(&self) -> usize {
+        self.graph.scopes().count()
+    }
+
+    /// Total number of listeners registered in all scopes.
+    pub fn listener_count(&self) -> usize {
+        self.graph.scopes().map(|scope| scope.listeners.values().map(Vec::len).sum::<usize>()).sum()
+    }
+
     pub fn global_scope(&self) -> &Scope {
         self.graph.scope_at(self.root_index).expect("No root scope in graph")
     }
@@ -291,15 +424,27 @@ impl ScopeGraph {
         let scope = self.graph.scope_at(scope_index).context("Scope not in graph")?;
         if let Some(triggered_listeners) = scope.listeners.get(updated_var) {
             for listener in triggered_listeners.clone() {
-                let required_variables = self.lookup_variables_in_scope(scope_index, &listener.needed_variables)?;
-                if let Err(err) = (*listener.f)(self, required_variables).context("Error while updating UI after state change") {
-                    error_handling_ctx::print_error(err);
+                match &mut self.deferred_listeners {
+                    Some(deferred) => {
+                        if !deferred.iter().any(|(index, l)| *index == scope_index && Rc::ptr_eq(l, &listener)) {
+                            deferred.push((scope_index, listener));
+                        }
+                    }
+                    None => self.call_listener(scope_index, &listener)?,
                 }
             }
         }
         Ok(())
     }
 
+    fn call_listener(&mut self, scope_index: ScopeIndex, listener: &Listener) -> Result<()> {
+        let required_variables = self.lookup_variables_in_scope(scope_index, &listener.needed_variables)?;
+        if let Err(err) = (*listener.f)(self, required_variables).context("Error while updating UI after state change") {
+            error_handling_ctx::print_error(err);
+        }
+        Ok(())
+    }
+
     /// Find the closest available scope that contains variable with the given name.
     pub fn find_scope_with_variable(&self, index: ScopeIndex, var_name: &VarName) -> Option<ScopeIndex> {
         let scope = self.graph.scope_at(index)?;
@@ -413,6 +558,10 @@ mod internal {
             self.hierarchy_relations.clear();
         }
 
+        pub fn scopes(&self) -> impl Iterator<Item = &Scope> {
+            self.scopes.values()
+        }
+
         pub fn add_scope(&mut self, scope: Scope) -> ScopeIndex {
             let idx = self.last_index;
             if let Some(ancestor) = scope.ancestor {
@@ -597,6 +746,64 @@ mod internal {
             output.push('}');
             output
         }
+
+        pub fn visualize_json(&self) -> serde_json::Value {
+            let scopes = self
+                .scopes
+                .iter()
+                .map(|(scope_index, scope)| {
+                    let listeners = scope
+                        .listeners
+                        .iter()
+                        .map(|(name, listeners)| {
+                            let needed_variables = listeners
+                                .iter()
+                                .map(|l| l.needed_variables.iter().map(|x| x.0.clone()).collect::<Vec<_>>())
+                                .collect::<Vec<_>>();
+                            (name.0.clone(), serde_json::json!(needed_variables))
+                        })
+                        .collect::<serde_json::Map<_, _>>();
+                    serde_json::json!({
+                        "index": scope_index.0,
+                        "name": scope.name,
+                        "ancestor": scope.ancestor.map(|x| x.0),
+                        "data": scope.data.iter().map(|(k, v)| (k.0.clone(), serde_json::json!(v.as_str()))).collect::<serde_json::Map<_, _>>(),
+                        "listeners": listeners,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let provided_attrs = self
+                .hierarchy_relations
+                .child_to_parent
+                .iter()
+                .flat_map(|(child, (parent, edges))| {
+                    edges.iter().map(move |edge| {
+                        serde_json::json!({
+                            "from": parent.0,
+                            "to": child.0,
+                            "attr": edge.attr_name.0,
+                            "expression": edge.expression.to_string(),
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let inheritance = self
+                .inheritance_relations
+                .child_to_parent
+                .iter()
+                .map(|(child, (parent, edge))| {
+                    serde_json::json!({
+                        "subscope": child.0,
+                        "superscope": parent.0,
+                        "references": edge.references.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            serde_json::json!({ "scopes": scopes, "provided_attrs": provided_attrs, "inheritance": inheritance })
+        }
     }
 }
 
@@ -605,6 +812,78 @@ mod test {
     use maplit::{hashmap, hashset};
 
     use super::*;
+    use crate::daemon_response::DaemonResponse;
+
+    #[test]
+    fn test_global_subscription() {
+        let globals = hashmap! {
+            "foo".into() => "1".into(),
+            "bar".into() => "a".into(),
+        };
+        let (send, _recv) = tokio::sync::mpsc::unbounded_channel();
+        let mut scope_graph = ScopeGraph::from_global_vars(globals, send);
+
+        let (sender, mut recv) = crate::daemon_response::create_pair();
+        scope_graph.subscribe_to_globals(vec!["foo".into()], false, sender);
+        scope_graph.update_global_value(&"bar".into(), "b".into()).unwrap();
+        scope_graph.update_global_value(&"foo".into(), "2".into()).unwrap();
+
+        assert_eq!(recv.try_recv().unwrap(), DaemonResponse::Success("foo: 1".to_string()));
+        assert_eq!(recv.try_recv().unwrap(), DaemonResponse::Success("foo: 2".to_string()));
+        assert!(recv.try_recv().is_err());
+
+        drop(recv);
+        scope_graph.update_global_value(&"foo".into(), "3".into()).unwrap();
+        assert!(scope_graph.global_subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_global_subscription_on_replace_and_disconnect() {
+        let (send, _recv) = tokio::sync::mpsc::unbounded_channel();
+        let mut scope_graph = ScopeGraph::from_global_vars(hashmap! { "foo".into() => "1".into() }, send);
+
+        let (sender, mut recv) = crate::daemon_response::create_pair();
+        scope_graph.subscribe_to_globals(vec!["foo".into()], false, sender);
+        scope_graph.replace_globals(hashmap! { "foo".into() => "2".into() }).unwrap();
+        scope_graph.clear(hashmap! { "foo".into() => "3".into() });
+        assert_eq!(recv.try_recv().unwrap(), DaemonResponse::Success("foo: 1".to_string()));
+        assert_eq!(recv.try_recv().unwrap(), DaemonResponse::Success("foo: 2".to_string()));
+        assert_eq!(recv.try_recv().unwrap(), DaemonResponse::Success("foo: 3".to_string()));
+
+        // a subscription to a variable that never changes is still removed once its client disconnects
+        drop(recv);
+        scope_graph.handle_scope_graph_event(ScopeGraphEvent::RemoveClosedSubscriptions);
+        assert!(scope_graph.global_subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_deferred_listeners() {
+        let globals = hashmap! {
+            "foo".into() => "1".into(),
+            "bar".into() => "a".into(),
+        };
+        let (send, _recv) = tokio::sync::mpsc::unbounded_channel();
+        let mut scope_graph = ScopeGraph::from_global_vars(globals, send);
+        let root_scope = scope_graph.root_index;
+
+        let calls = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let listener_calls = calls.clone();
+        let listener = Listener {
+            needed_variables: vec!["foo".into(), "bar".into()],
+            f: Box::new(move |_, values| {
+                listener_calls.borrow_mut().push(format!("{}{}", values["foo"], values["bar"]));
+                Ok(())
+            }),
+        };
+        scope_graph.register_listener(root_scope, listener).unwrap();
+
+        scope_graph.with_deferred_listeners(|scope_graph| {
+            scope_graph.update_global_value(&"foo".into(), "2".into()).unwrap();
+            scope_graph.update_global_value(&"bar".into(), "b".into()).unwrap();
+        });
+        // the listener is called once when it is registered, and then only once for both updates
+        assert_eq!(*calls.borrow(), vec!["1a".to_string(), "2b".to_string()]);
+    }
 
     #[test]
     fn test_nested_inheritance() {
@@ -1,5 +1,7 @@
+use anyhow::{bail, Context, Result};
 use extend::ext;
 use itertools::Itertools;
+use serde_json::Value;
 use std::fmt::Write;
 
 #[macro_export]
@@ -141,9 +143,91 @@ pub fn unindent(text: &str) -> String {
     result
 }
 
+/// Set the value at the given path within a json value, creating objects along the way where necessary.
+/// Array elements are addressed by their index.
+pub fn json_set_path(target: &mut Value, path: &[&str], value: Value) -> Result<()> {
+    let (key, rest) = match path.split_first() {
+        Some(x) => x,
+        None => {
+            *target = value;
+            return Ok(());
+        }
+    };
+    match target {
+        Value::Object(fields) => json_set_path(fields.entry(*key).or_insert(Value::Null), rest, value),
+        Value::Array(items) => {
+            let index: usize = key.parse().with_context(|| format!("Expected an array index, but got `{}`", key))?;
+            let len = items.len();
+            let item =
+                items.get_mut(index).with_context(|| format!("Index {} is out of bounds for array of length {}", index, len))?;
+            json_set_path(item, rest, value)
+        }
+        Value::Null => {
+            *target = Value::Object(serde_json::Map::new());
+            json_set_path(target, path, value)
+        }
+        other => bail!("Can't set field `{}` of {}, as it is not an object", key, other),
+    }
+}
+
+/// Apply a json merge patch, as specified in RFC 7386: fields of the patch replace the fields of the target,
+/// objects are merged recursively, and `null` removes a field.
+pub fn json_merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch_fields) => {
+            if !target.is_object() {
+                *target = Value::Object(serde_json::Map::new());
+            }
+            if let Value::Object(fields) = target {
+                for (key, value) in patch_fields {
+                    if value.is_null() {
+                        fields.remove(&key);
+                    } else {
+                        json_merge_patch(fields.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+/// Format a duration given in seconds in a short, human readable way, i.e. `2h 5m`.
+pub fn format_duration_short(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{replace_env_var_references, unindent};
+    use super::{format_duration_short, json_merge_patch, json_set_path, replace_env_var_references, unindent};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_set_path() {
+        let mut value = json!({ "settings": { "volume": 20, "muted": false }, "items": [{ "name": "a" }] });
+        json_set_path(&mut value, &["settings", "volume"], json!(40)).unwrap();
+        json_set_path(&mut value, &["items", "0", "name"], json!("b")).unwrap();
+        json_set_path(&mut value, &["new", "field"], json!(true)).unwrap();
+        assert_eq!(
+            value,
+            json!({ "settings": { "volume": 40, "muted": false }, "items": [{ "name": "b" }], "new": { "field": true } })
+        );
+
+        assert!(json_set_path(&mut value, &["items", "5"], json!(1)).is_err());
+        assert!(json_set_path(&mut value, &["settings", "volume", "x"], json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_json_merge_patch() {
+        let mut value = json!({ "a": "b", "c": { "d": "e", "f": "g" } });
+        json_merge_patch(&mut value, json!({ "a": "z", "c": { "f": null } }));
+        assert_eq!(value, json!({ "a": "z", "c": { "d": "e" } }));
+    }
 
     #[test]
     fn test_replace_env_var_references() {
@@ -162,4 +246,12 @@ mod test {
             line two";
         assert_eq!("line one\nline two", unindent(indented));
     }
+
+    #[test]
+    fn test_format_duration_short() {
+        assert_eq!(format_duration_short(42), "42s");
+        assert_eq!(format_duration_short(125), "2m");
+        assert_eq!(format_duration_short(2 * 3600 + 5 * 60 + 3), "2h 5m");
+        assert_eq!(format_duration_short(3 * 86400 + 7200), "3d 2h");
+    }
 }
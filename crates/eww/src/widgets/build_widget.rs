@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use codespan_reporting::diagnostic::Severity;
-use eww_shared_util::{AttrName, Spanned};
+use eww_shared_util::{AttrName, Spanned, VarName};
 use gdk::prelude::Cast;
 use gtk::{
     prelude::{BoxExt, ContainerExt, WidgetExt, WidgetExtManual},
@@ -9,7 +9,7 @@ use gtk::{
 use itertools::Itertools;
 use maplit::hashmap;
 use simplexpr::{dynval::DynVal, SimplExpr};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
 use yuck::{
     config::{
         attributes::AttrEntry,
@@ -31,6 +31,11 @@ use crate::{
 
 use super::widget_definitions::{resolve_orientable_attrs, resolve_range_attrs, resolve_widget_attrs};
 
+/// Variable in the scope of a widget with a `:refresh-interval`, which all attributes of that widget depend on.
+/// It is notified about a change in every interval, re-evaluating the attributes, i.e. to update the result of `now()`.
+/// This is not a valid variable name in yuck, so it can't clash with any variable of the configuration.
+pub const REFRESH_VAR_NAME: &str = "refresh-interval()";
+
 pub struct BuilderArgs<'a> {
     pub calling_scope: ScopeIndex,
     pub widget_use: BasicWidgetUse,
@@ -38,11 +43,10 @@ pub struct BuilderArgs<'a> {
     pub unhandled_attrs: HashMap<AttrName, AttrEntry>,
     pub widget_defs: Rc<HashMap<String, WidgetDefinition>>,
     pub custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
+    /// Whether the widget has a `:refresh-interval`, in which case all of its attributes depend on [`REFRESH_VAR_NAME`].
+    pub refreshing: bool,
 }
 
-// TODO in case of custom widgets, we should add a validation step where
-// warnings for unknown attributes (attributes not expected by the widget) are emitted.
-
 /// Build a [`gtk::Widget`] out of a [`WidgetUse`].
 /// This will set up scopes in the [`ScopeGraph`], register all the listeners there,
 /// and recursively generate all the widgets and child widgets.
@@ -65,6 +69,59 @@ pub fn build_gtk_widget(
     }
 }
 
+/// Check a [`WidgetUse`] for the errors [`build_gtk_widget`] would run into, without building any gtk widgets.
+/// This finds unknown widgets, misplaced `children` and `for` uses, and containers with the wrong number of children.
+pub fn check_widget_use(
+    widget_defs: &HashMap<String, WidgetDefinition>,
+    widget_use: &WidgetUse,
+    is_in_definition: bool,
+) -> Vec<anyhow::Error> {
+    let widget_use = match widget_use {
+        WidgetUse::Basic(widget_use) => widget_use,
+        WidgetUse::Loop(_) | WidgetUse::Children(_) => {
+            return vec![anyhow::anyhow!(DiagError(gen_diagnostic! {
+                msg = "This widget can only be used as a child of some container widget such as box",
+                label = widget_use.span(),
+                note = "Hint: try wrapping this in a `box`"
+            }))]
+        }
+    };
+    let mut errors: Vec<anyhow::Error> = Vec::new();
+    let name = widget_use.name.as_str();
+    if !widget_defs.contains_key(name) && !widget_definitions::BUILTIN_WIDGET_NAMES.contains(&name) {
+        errors.push(
+            DiagError(gen_diagnostic! {
+                msg = format!("referenced unknown widget `{}`", name),
+                label = widget_use.name_span => "Used here",
+            })
+            .into(),
+        );
+    } else if widget_definitions::SINGLE_CHILD_WIDGET_NAMES.contains(&name) && widget_use.children.len() > 1 {
+        errors.push(
+            DiagError(gen_diagnostic! {
+                msg = format!("{} can only have one child", name),
+                label = widget_use.children_span() => format!("Was given {} children here", widget_use.children.len())
+            })
+            .into(),
+        );
+    } else if name == widget_definitions::WIDGET_NAME_CENTERBOX && widget_use.children.len() != 3 {
+        errors.push(DiagError(gen_diagnostic!("centerbox must contain exactly 3 elements", widget_use.span)).into());
+    }
+
+    for child in &widget_use.children {
+        match child {
+            WidgetUse::Children(child) if !is_in_definition => errors.push(anyhow::anyhow!(DiagError(gen_diagnostic! {
+                msg = "`children` can only be used within a widget definition",
+                label = child.span
+            }))),
+            WidgetUse::Children(_) => {}
+            WidgetUse::Loop(child) => errors.extend(check_widget_use(widget_defs, &child.body, is_in_definition)),
+            WidgetUse::Basic(_) => errors.extend(check_widget_use(widget_defs, child, is_in_definition)),
+        }
+    }
+    errors
+}
+
 fn build_basic_gtk_widget(
     graph: &mut ScopeGraph,
     widget_defs: Rc<HashMap<String, WidgetDefinition>>,
@@ -124,9 +181,25 @@ fn build_builtin_gtk_widget(
     graph: &mut ScopeGraph,
     widget_defs: Rc<HashMap<String, WidgetDefinition>>,
     calling_scope: ScopeIndex,
-    widget_use: BasicWidgetUse,
+    mut widget_use: BasicWidgetUse,
     custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
 ) -> Result<gtk::Widget> {
+    let refresh_interval = widget_use
+        .attrs
+        .primitive_optional::<DynVal, _>("refresh-interval")?
+        .map(|interval| interval.as_duration())
+        .transpose()?;
+    // The attributes of a refreshing widget are evaluated in a scope of their own, which contains the variable they all depend on
+    let calling_scope = match refresh_interval {
+        Some(_) => graph.register_new_scope(
+            "refresh-interval".to_string(),
+            Some(calling_scope),
+            calling_scope,
+            hashmap! { AttrName(REFRESH_VAR_NAME.to_string()) => SimplExpr::synth_string("") },
+        )?,
+        None => calling_scope,
+    };
+
     let mut bargs = BuilderArgs {
         unhandled_attrs: widget_use.attrs.attrs.clone(),
         scope_graph: graph,
@@ -134,6 +207,7 @@ fn build_builtin_gtk_widget(
         widget_use,
         widget_defs,
         custom_widget_invocation,
+        refreshing: refresh_interval.is_some(),
     };
     let gtk_widget = widget_definitions::widget_use_to_gtk_widget(&mut bargs)?;
 
@@ -162,6 +236,10 @@ fn build_builtin_gtk_widget(
     };
     resolve_widget_attrs(&mut bargs, &gtk_widget)?;
 
+    if let Some(refresh_interval) = refresh_interval {
+        start_refresh_timer(bargs.scope_graph, &gtk_widget, calling_scope, refresh_interval);
+    }
+
     for (attr_name, attr_entry) in bargs.unhandled_attrs {
         let diag = error_handling_ctx::stringify_diagnostic(gen_diagnostic! {
             kind =  Severity::Warning,
@@ -173,6 +251,24 @@ fn build_builtin_gtk_widget(
     Ok(gtk_widget)
 }
 
+/// Periodically re-evaluate the attributes of a widget with a `:refresh-interval`, until the widget is destroyed.
+fn start_refresh_timer(graph: &ScopeGraph, gtk_widget: &gtk::Widget, refresh_scope: ScopeIndex, interval: Duration) {
+    let scope_graph_sender = graph.event_sender.clone();
+    let source_id = glib::timeout_add_local(interval, move || {
+        let evt = ScopeGraphEvent::NotifyValueChanged(refresh_scope, VarName::from(REFRESH_VAR_NAME));
+        glib::Continue(scope_graph_sender.send(evt).is_ok())
+    });
+
+    let source_id = RefCell::new(Some(source_id));
+    let scope_graph_sender = graph.event_sender.clone();
+    gtk_widget.connect_destroy(move |_| {
+        if let Some(source_id) = source_id.take() {
+            source_id.remove();
+        }
+        let _ = scope_graph_sender.send(ScopeGraphEvent::RemoveScope(refresh_scope));
+    });
+}
+
 /// If a gtk widget can take children (→ it is a [`gtk::Container`]) we need to add the provided `widget_use_children`
 /// into that container. Those children might be uses of the special `children`-[`WidgetUse`], which will get expanded here, too.
 fn populate_widget_children(
@@ -183,36 +279,43 @@ fn populate_widget_children(
     widget_use_children: Vec<WidgetUse>,
     custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
 ) -> Result<()> {
+    let mut errors = Vec::new();
     for child in widget_use_children {
-        match child {
-            WidgetUse::Children(child) => {
-                build_children_special_widget(
-                    tree,
-                    widget_defs.clone(),
-                    calling_scope,
-                    child,
-                    gtk_container,
-                    custom_widget_invocation.clone().context("Not in a custom widget invocation")?,
-                )?;
-            }
-            WidgetUse::Loop(child) => {
-                build_loop_special_widget(
-                    tree,
-                    widget_defs.clone(),
-                    calling_scope,
-                    child,
-                    gtk_container,
-                    custom_widget_invocation.clone(),
-                )?;
-            }
-            _ => {
-                let child_widget =
-                    build_gtk_widget(tree, widget_defs.clone(), calling_scope, child, custom_widget_invocation.clone())?;
-                gtk_container.add(&child_widget);
+        let result: Result<()> = try {
+            match child {
+                WidgetUse::Children(child) => {
+                    build_children_special_widget(
+                        tree,
+                        widget_defs.clone(),
+                        calling_scope,
+                        child,
+                        gtk_container,
+                        custom_widget_invocation.clone().context("Not in a custom widget invocation")?,
+                    )?;
+                }
+                WidgetUse::Loop(child) => {
+                    build_loop_special_widget(
+                        tree,
+                        widget_defs.clone(),
+                        calling_scope,
+                        child,
+                        gtk_container,
+                        custom_widget_invocation.clone(),
+                    )?;
+                }
+                _ => {
+                    let child_widget =
+                        build_gtk_widget(tree, widget_defs.clone(), calling_scope, child, custom_widget_invocation.clone())?;
+                    gtk_container.add(&child_widget);
+                }
             }
+        };
+        // keep building the other children, to report the errors of all of them at once
+        if let Err(err) = result {
+            errors.push(err);
         }
     }
-    Ok(())
+    error_handling_ctx::combine_errors(errors)
 }
 
 fn build_loop_special_widget(
@@ -238,7 +341,7 @@ fn build_loop_special_widget(
                 move |tree, values| {
                     let elements_value = elements_expr
                         .eval(&values)?
-                        .as_json_value()?
+                        .as_json_ref()?
                         .as_array()
                         .context("Not an array value")?
                         .iter()
@@ -259,7 +362,7 @@ fn build_loop_special_widget(
                             Some(calling_scope),
                             calling_scope,
                             hashmap! {
-                                element_name.clone().into() => SimplExpr::Literal(DynVal(element.0, elements_expr_span))
+                                element_name.clone().into() => SimplExpr::Literal(element.at(elements_expr_span))
                             },
                         )?;
                         created_child_scopes.push(scope);
@@ -33,10 +33,13 @@ macro_rules! def_widget {
                 if attr_map.values().any(|x| x.is_some()) {
 
                     // Get all the variables that are referred to in any of the attributes expressions
-                    let required_vars: Vec<eww_shared_util::VarName> = attr_map
+                    let mut required_vars: Vec<eww_shared_util::VarName> = attr_map
                         .values()
                         .flat_map(|expr| expr.as_ref().map(|x| x.collect_var_refs()).unwrap_or_default())
                         .collect();
+                    if $args.refreshing {
+                        required_vars.push(eww_shared_util::VarName::from($crate::widgets::build_widget::REFRESH_VAR_NAME));
+                    }
 
                     $args.scope_graph.register_listener(
                         $args.calling_scope,
@@ -1,4 +1,11 @@
+use anyhow::{Context, Result};
+use eww_shared_util::VarName;
+use once_cell::sync::OnceCell;
+use simplexpr::dynval::DynVal;
 use std::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::app::DaemonCommand;
 
 pub mod build_widget;
 pub mod circular_progressbar;
@@ -7,16 +14,29 @@ pub mod graph;
 pub mod transform;
 pub mod widget_definitions;
 
+/// Sender used by event handlers to send [`DaemonCommand`]s to the app directly. This is set when the daemon is initialized.
+pub static APP_EVT_SEND: OnceCell<UnboundedSender<DaemonCommand>> = OnceCell::new();
+
 /// Run a command that was provided as an attribute.
 /// This command may use placeholders which will be replaced by the values of the arguments given.
 /// This can either be the placeholder `{}`, which will be replaced by the first argument,
 /// Or a placeholder like `{0}`, `{1}`, etc, which will refer to the respective argument.
+///
+/// Commands of the form `set:name=value` update the variable directly, without spawning a shell.
 pub(self) fn run_command<T>(timeout: std::time::Duration, cmd: &str, args: &[T])
 where
     T: 'static + std::fmt::Display + Send + Sync + Clone,
 {
     use wait_timeout::ChildExt;
     let cmd = replace_placeholders(cmd, args);
+    if let Some(mapping) = parse_set_var_command(&cmd) {
+        let result: Result<_> = try {
+            let evt_send = APP_EVT_SEND.get().context("Variables can only be updated from within the eww daemon")?;
+            evt_send.send(DaemonCommand::UpdateVars(vec![mapping?], None))?;
+        };
+        crate::print_result_err!(format!("while running command {}", cmd), result);
+        return;
+    }
     std::thread::Builder::new()
         .name("command-execution-thread".to_string())
         .spawn(move || {
@@ -39,6 +59,11 @@ where
         .expect("Failed to start command-execution-thread");
 }
 
+/// Parse a command of the form `set:name=value`, which updates a variable instead of running a shell command.
+fn parse_set_var_command(cmd: &str) -> Option<Result<(VarName, DynVal)>> {
+    cmd.trim_start().strip_prefix("set:").map(crate::opts::parse_var_update_arg)
+}
+
 fn replace_placeholders<T>(cmd: &str, args: &[T]) -> String
 where
     T: 'static + std::fmt::Display + Send + Sync + Clone,
@@ -62,4 +87,13 @@ mod test {
         assert_eq!("bar foo baz", replace_placeholders("{0} foo {1}", &["bar", "baz"]),);
         assert_eq!("baz foo bar", replace_placeholders("{1} foo {0}", &["bar", "baz"]),);
     }
+
+    #[test]
+    fn test_parse_set_var_command() {
+        let (name, value) = parse_set_var_command("set:foo=hello world").unwrap().unwrap();
+        assert_eq!(name, VarName::from("foo"));
+        assert_eq!(value.as_str(), "hello world");
+        assert!(parse_set_var_command("set:foo").unwrap().is_err());
+        assert!(parse_set_var_command("eww update foo=bar").is_none());
+    }
 }
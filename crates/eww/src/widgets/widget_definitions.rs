@@ -82,6 +82,16 @@ pub const BUILTIN_WIDGET_NAMES: &[&str] = &[
     WIDGET_NAME_OVERLAY,
 ];
 
+/// Builtin widgets that are a [`gtk::Bin`], and can thus only have a single child
+pub const SINGLE_CHILD_WIDGET_NAMES: &[&str] = &[
+    WIDGET_NAME_EVENTBOX,
+    WIDGET_NAME_BUTTON,
+    WIDGET_NAME_EXPANDER,
+    WIDGET_NAME_REVEALER,
+    WIDGET_NAME_SCROLL,
+    WIDGET_NAME_CHECKBOX,
+];
+
 //// widget definitions
 pub(super) fn widget_use_to_gtk_widget(bargs: &mut BuilderArgs) -> Result<gtk::Widget> {
     let gtk_widget = match bargs.widget_use.name.as_str() {
@@ -575,7 +585,7 @@ fn build_gtk_overlay(bargs: &mut BuilderArgs) -> Result<gtk::Overlay> {
     }
 }
 
-const WIDGET_NAME_CENTERBOX: &str = "centerbox";
+pub const WIDGET_NAME_CENTERBOX: &str = "centerbox";
 /// @widget centerbox
 /// @desc a box that must contain exactly three children, which will be layed out at the start, center and end of the container.
 fn build_center_box(bargs: &mut BuilderArgs) -> Result<gtk::Box> {
@@ -0,0 +1,46 @@
+use eww_shared_util::VarName;
+use simplexpr::dynval::DynVal;
+use std::collections::HashMap;
+use yuck::{
+    config::{monitor::MonitorIdentifier, window_geometry::AnchorPoint},
+    value::Coords,
+};
+
+/// Everything a window instance is opened with.
+/// A window definition may be opened multiple times, as long as every instance has its own id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowArguments {
+    /// Name of the window definition this is an instance of
+    pub window_name: String,
+    /// Id of this instance. Defaults to the name of the window.
+    pub instance_id: String,
+    pub pos: Option<Coords>,
+    pub size: Option<Coords>,
+    pub anchor: Option<AnchorPoint>,
+    pub monitor: Option<MonitorIdentifier>,
+    /// Values given with `--arg`, which are available as variables within the window
+    pub args: HashMap<VarName, DynVal>,
+}
+
+impl WindowArguments {
+    /// Arguments for opening the window with the given name, without any overrides or arguments.
+    pub fn new(window_name: String) -> Self {
+        WindowArguments {
+            instance_id: window_name.clone(),
+            window_name,
+            pos: None,
+            size: None,
+            anchor: None,
+            monitor: None,
+            args: HashMap::new(),
+        }
+    }
+
+    /// Use the given instance id instead of the window name, if one is given.
+    pub fn with_instance_id(mut self, instance_id: Option<String>) -> Self {
+        if let Some(instance_id) = instance_id {
+            self.instance_id = instance_id;
+        }
+        self
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{bail, Result};
+use eww_shared_util::VarName;
+use simplexpr::dynval::DynVal;
+use std::collections::HashMap;
+use yuck::config::{
+    backend_window_options::BackendWindowOptions,
+    monitor::MonitorIdentifier,
+    window_definition::{WindowDefinition, WindowStacking},
+    window_geometry::WindowGeometry,
+};
+
+use crate::window_arguments::WindowArguments;
+
+/// A window definition, with all of its attributes evaluated using the arguments the window is opened with.
+/// This is what the window actually gets initialized with.
+#[derive(Debug, Clone)]
+pub struct WindowInitiator {
+    pub name: String,
+    pub backend_options: BackendWindowOptions,
+    pub geometry: Option<WindowGeometry>,
+    /// The values of all arguments of the window, which are made available in the scope of the window
+    pub local_variables: HashMap<VarName, DynVal>,
+    pub monitor: Option<MonitorIdentifier>,
+    pub resizable: bool,
+    pub stacking: WindowStacking,
+}
+
+impl WindowInitiator {
+    pub fn new(window_def: &WindowDefinition, args: &WindowArguments) -> Result<Self> {
+        let local_variables = get_local_variables(window_def, &args.args)?;
+        let geometry = window_def.eval_geometry(&local_variables)?.map(|x| x.override_if_given(args.anchor, args.pos, args.size));
+        let monitor = match &args.monitor {
+            Some(monitor) => Some(monitor.clone()),
+            None => window_def.eval_monitor(&local_variables)?,
+        };
+        Ok(WindowInitiator {
+            name: window_def.name.clone(),
+            backend_options: window_def.backend_options.clone(),
+            geometry,
+            local_variables,
+            monitor,
+            resizable: window_def.resizable,
+            stacking: window_def.stacking,
+        })
+    }
+}
+
+/// Check the given arguments against the arguments the window expects, and get the values of all of them.
+/// Optional arguments that were not given default to an empty string, like they do for widgets.
+fn get_local_variables(window_def: &WindowDefinition, args: &HashMap<VarName, DynVal>) -> Result<HashMap<VarName, DynVal>> {
+    if let Some(unknown) = args.keys().find(|name| !window_def.expected_args.iter().any(|spec| spec.name.0 == name.0)) {
+        bail!("Window `{}` has no argument named `{}`", window_def.name, unknown);
+    }
+    let mut local_variables = HashMap::new();
+    for spec in &window_def.expected_args {
+        let name = VarName::from(spec.name.clone());
+        match args.get(&name) {
+            Some(value) => local_variables.insert(name, value.clone()),
+            None if spec.optional => local_variables.insert(name, DynVal::from("")),
+            None => bail!(
+                "Missing required argument `{}` for window `{}`. Pass it using `--arg {}=<value>`",
+                name,
+                window_def.name,
+                name
+            ),
+        };
+    }
+    Ok(local_variables)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yuck::parser::from_ast::FromAst;
+
+    #[test]
+    fn test_window_arguments() {
+        let ast =
+            yuck::parser::parse_string(0, r#"(defwindow bar [monitor ?title] :monitor monitor (label :text "hi"))"#).unwrap();
+        let window_def = WindowDefinition::from_ast(ast).unwrap();
+
+        let mut args = WindowArguments::new("bar".to_string());
+        assert!(WindowInitiator::new(&window_def, &args).is_err());
+
+        args.args.insert(VarName::from("monitor"), DynVal::from("1"));
+        let initiator = WindowInitiator::new(&window_def, &args).unwrap();
+        assert_eq!(initiator.monitor, Some(MonitorIdentifier::Numeric(1)));
+        assert_eq!(initiator.local_variables.get(&VarName::from("title")), Some(&DynVal::from("")));
+
+        args.args.insert(VarName::from("other"), DynVal::from("1"));
+        assert!(WindowInitiator::new(&window_def, &args).is_err());
+    }
+}
@@ -52,6 +52,8 @@ pub enum SimplExpr {
     IfElse(Span, Box<SimplExpr>, Box<SimplExpr>, Box<SimplExpr>),
     JsonAccess(Span, AccessType, Box<SimplExpr>, Box<SimplExpr>),
     FunctionCall(Span, String, Vec<SimplExpr>),
+    /// An anonymous function (`x => x.name`), only usable as the last argument of a function call.
+    Lambda(Span, Vec<VarName>, Box<SimplExpr>),
 }
 
 impl std::fmt::Display for SimplExpr {
@@ -77,6 +79,8 @@ impl std::fmt::Display for SimplExpr {
             SimplExpr::FunctionCall(_, function_name, args) => {
                 write!(f, "{}({})", function_name, args.iter().join(", "))
             }
+            SimplExpr::Lambda(_, params, body) if params.len() == 1 => write!(f, "({} => {})", params[0], body),
+            SimplExpr::Lambda(_, params, body) => write!(f, "(({}) => {})", params.iter().join(", "), body),
             SimplExpr::JsonArray(_, values) => write!(f, "[{}]", values.iter().join(", ")),
             SimplExpr::JsonObject(_, entries) => {
                 write!(f, "{{{}}}", entries.iter().map(|(k, v)| format!("{}: {}", k, v)).join(", "))
@@ -86,12 +90,12 @@ impl std::fmt::Display for SimplExpr {
 }
 impl SimplExpr {
     pub fn literal(span: Span, s: String) -> Self {
-        Self::Literal(DynVal(s, span))
+        Self::Literal(DynVal::from_string(s).at(span))
     }
 
     /// Construct a synthetic simplexpr from a literal string, without adding any relevant span information (uses [`Span::DUMMY`])
     pub fn synth_string(s: impl Into<String>) -> Self {
-        Self::Literal(DynVal(s.into(), Span::DUMMY))
+        Self::Literal(DynVal::from_string(s.into()))
     }
 
     /// Construct a synthetic simplexpr from a literal dynval, without adding any relevant span information (uses [`Span::DUMMY`])
@@ -113,6 +117,7 @@ impl SimplExpr {
             UnaryOp(_, _, x) => x.references_var(var),
             IfElse(_, a, b, c) => a.references_var(var) || b.references_var(var) || c.references_var(var),
             VarRef(_, x) => x == var,
+            Lambda(_, params, body) => !params.contains(var) && body.references_var(var),
         }
     }
 
@@ -135,6 +140,7 @@ impl SimplExpr {
                 k.collect_var_refs_into(dest);
                 v.collect_var_refs_into(dest);
             }),
+            Lambda(_, params, body) => dest.extend(body.collect_var_refs().into_iter().filter(|x| !params.contains(x))),
             Literal(_) => {}
         };
     }
@@ -159,6 +165,7 @@ impl Spanned for SimplExpr {
             SimplExpr::IfElse(span, ..) => *span,
             SimplExpr::JsonAccess(span, ..) => *span,
             SimplExpr::FunctionCall(span, ..) => *span,
+            SimplExpr::Lambda(span, ..) => *span,
         }
     }
 }
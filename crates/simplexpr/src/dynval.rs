@@ -1,7 +1,8 @@
 use eww_shared_util::{Span, Spanned};
 use itertools::Itertools;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, fmt, iter::FromIterator, str::FromStr};
+use std::{borrow::Cow, convert::TryFrom, fmt, iter::FromIterator, str::FromStr};
 
 pub type Result<T> = std::result::Result<T, ConversionError>;
 
@@ -24,44 +25,101 @@ impl ConversionError {
 }
 impl Spanned for ConversionError {
     fn span(&self) -> Span {
-        self.value.1
+        self.value.span()
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Eq)]
-pub struct DynVal(pub String, pub Span);
+/// The typed representation of a [`DynVal`], parsed once when the value is created.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypedValue {
+    String(String),
+    /// Always finite, as NaN isn't equal to itself. Non-finite numbers are kept as strings instead.
+    Number(f64),
+    Bool(bool),
+    /// Json arrays, objects and null
+    Json(serde_json::Value),
+}
+
+impl TypedValue {
+    /// Parse the typed representation of a string, returning [`None`] if it is just a string.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "true" => Some(TypedValue::Bool(true)),
+            "false" => Some(TypedValue::Bool(false)),
+            "null" => Some(TypedValue::Json(serde_json::Value::Null)),
+            _ if s.starts_with('[') || s.starts_with('{') => serde_json::from_str(s).ok().map(TypedValue::Json),
+            _ => s.parse().ok().filter(|n: &f64| n.is_finite()).map(TypedValue::Number),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            TypedValue::String(s) => s.clone(),
+            TypedValue::Number(n) => n.to_string(),
+            TypedValue::Bool(b) => b.to_string(),
+            TypedValue::Json(json) => json.to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DynVal {
+    value: TypedValue,
+    /// Textual representation of non-string values. Kept when the value was parsed from a string,
+    /// so it is displayed exactly as given (i.e. `1.50`), and generated on first use otherwise.
+    text: OnceCell<String>,
+    span: Span,
+}
 
 impl From<String> for DynVal {
     fn from(s: String) -> Self {
-        DynVal(s, Span::DUMMY)
+        DynVal::from_string(s)
     }
 }
 
 impl fmt::Display for DynVal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.as_str())
     }
 }
 impl fmt::Debug for DynVal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "\"{}\"", self.0)
+        write!(f, "\"{}\"", self.as_str())
     }
 }
 
 /// Manually implement equality, to allow for values in different formats (i.e. "1" and "1.0") to still be considered as equal.
 impl std::cmp::PartialEq<Self> for DynVal {
     fn eq(&self, other: &Self) -> bool {
-        if let (Ok(a), Ok(b)) = (self.as_f64(), other.as_f64()) {
-            a == b
-        } else {
-            self.0 == other.0
+        match (&self.value, &other.value) {
+            (TypedValue::Number(a), TypedValue::Number(b)) => a == b,
+            (TypedValue::Json(a), TypedValue::Json(b)) => a == b,
+            _ => match (self.parse_f64(), other.parse_f64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.as_str() == other.as_str(),
+            },
         }
     }
 }
+impl Eq for DynVal {}
+
+/// Values are serialized as their text, to keep the format used for ipc and persisted state.
+impl Serialize for DynVal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        (self.as_str(), self.span).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DynVal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let (text, span) = <(String, Span)>::deserialize(deserializer)?;
+        Ok(DynVal::from_string(text).at(span))
+    }
+}
 
 impl FromIterator<DynVal> for DynVal {
     fn from_iter<T: IntoIterator<Item = DynVal>>(iter: T) -> Self {
-        DynVal(iter.into_iter().join(""), Span::DUMMY)
+        DynVal::from_string(iter.into_iter().join(""))
     }
 }
 
@@ -84,97 +142,198 @@ impl<E, T: FromStr<Err = E>> FromDynVal for T {
     type Err = E;
 
     fn from_dynval(x: &DynVal) -> std::result::Result<Self, Self::Err> {
-        x.0.parse()
+        x.as_str().parse()
     }
 }
 
-macro_rules! impl_dynval_from {
+macro_rules! impl_dynval_from_number {
     ($($t:ty),*) => {
         $(impl From<$t> for DynVal {
-            fn from(x: $t) -> Self { DynVal(x.to_string(), Span::DUMMY) }
+            fn from(x: $t) -> Self { DynVal::typed(TypedValue::Number(x as f64), Some(x.to_string())) }
         })*
     };
 }
 
-impl_dynval_from!(bool, i32, u32, f32, u8, f64, &str);
+impl_dynval_from_number!(i32, u32, i64, f32, u8);
 
+impl From<f64> for DynVal {
+    fn from(x: f64) -> Self {
+        DynVal::typed(TypedValue::Number(x), None)
+    }
+}
+
+impl From<bool> for DynVal {
+    fn from(x: bool) -> Self {
+        DynVal::typed(TypedValue::Bool(x), None)
+    }
+}
+
+impl From<&str> for DynVal {
+    fn from(x: &str) -> Self {
+        DynVal::from_string(x.to_string())
+    }
+}
+
+/// Turns the value into a [`DynVal`] holding its json representation. Json strings stay quoted.
 impl TryFrom<serde_json::Value> for DynVal {
     type Error = serde_json::Error;
 
     fn try_from(value: serde_json::Value) -> std::result::Result<Self, Self::Error> {
-        Ok(DynVal(serde_json::to_string(&value)?, Span::DUMMY))
+        match value {
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) | serde_json::Value::Null => {
+                Ok(DynVal::typed(TypedValue::Json(value), None))
+            }
+            _ => Ok(DynVal::from_string(serde_json::to_string(&value)?)),
+        }
     }
 }
 
 impl From<std::time::Duration> for DynVal {
     fn from(d: std::time::Duration) -> Self {
-        DynVal(format!("{}ms", d.as_millis()), Span::DUMMY)
+        DynVal::from_string(format!("{}ms", d.as_millis()))
     }
 }
 
+/// Turns the value into a [`DynVal`], using the contents of json strings rather than their json representation.
 impl From<&serde_json::Value> for DynVal {
     fn from(v: &serde_json::Value) -> Self {
-        DynVal(
-            v.as_str()
-                .map(|x| x.to_string())
-                .or_else(|| serde_json::to_string(v).ok())
-                .unwrap_or_else(|| "<invalid json value>".to_string()),
-            Span::DUMMY,
-        )
+        match v {
+            serde_json::Value::String(s) => DynVal::typed(TypedValue::String(s.clone()), None),
+            serde_json::Value::Bool(b) => DynVal::from(*b),
+            serde_json::Value::Number(n) => match n.as_f64() {
+                Some(x) => DynVal::typed(TypedValue::Number(x), Some(n.to_string())),
+                None => DynVal::from_string(n.to_string()),
+            },
+            _ => DynVal::typed(TypedValue::Json(v.clone()), None),
+        }
     }
 }
 
 impl Spanned for DynVal {
     fn span(&self) -> Span {
-        self.1
+        self.span
     }
 }
 
 impl DynVal {
+    fn typed(value: TypedValue, text: Option<String>) -> Self {
+        if let TypedValue::Number(n) = value {
+            if !n.is_finite() {
+                return DynVal::typed(TypedValue::String(text.unwrap_or_else(|| n.to_string())), None);
+            }
+        }
+        let text = match text {
+            Some(text) => OnceCell::with_value(text),
+            None => OnceCell::new(),
+        };
+        DynVal { value, text, span: Span::DUMMY }
+    }
+
     pub fn at(mut self, span: Span) -> Self {
-        self.1 = span;
+        self.span = span;
         self
     }
 
     pub fn at_if_dummy(mut self, span: Span) -> Self {
-        if self.1.is_dummy() {
-            self.1 = span;
+        if self.span.is_dummy() {
+            self.span = span;
         }
         self
     }
 
+    /// Create a value from a string, parsing it into a number, boolean or json array/object if possible.
     pub fn from_string(s: String) -> Self {
-        DynVal(s, Span::DUMMY)
+        match TypedValue::parse(&s) {
+            Some(value) => DynVal::typed(value, Some(s)),
+            None => DynVal::typed(TypedValue::String(s), None),
+        }
     }
 
     pub fn read_as<E, T: FromDynVal<Err = E>>(&self) -> std::result::Result<T, E> {
         T::from_dynval(self)
     }
 
+    /// The typed representation of this value.
+    pub fn value(&self) -> &TypedValue {
+        &self.value
+    }
+
+    /// The textual representation of this value.
+    pub fn as_str(&self) -> &str {
+        match &self.value {
+            TypedValue::String(s) => s,
+            value => self.text.get_or_init(|| value.to_text()),
+        }
+    }
+
     pub fn into_inner(self) -> String {
-        self.0
+        let DynVal { value, text, .. } = self;
+        match value {
+            TypedValue::String(s) => s,
+            value => text.into_inner().unwrap_or_else(|| value.to_text()),
+        }
     }
 
     /// This will never fail
     pub fn as_string(&self) -> Result<String> {
-        Ok(self.0.to_owned())
+        Ok(self.as_str().to_owned())
+    }
+
+    /// The numeric value, if this is a number or a string containing one.
+    pub(crate) fn parse_f64(&self) -> Option<f64> {
+        match self.value {
+            TypedValue::Number(n) => Some(n),
+            TypedValue::String(ref s) => s.parse().ok().filter(|n: &f64| n.is_finite()),
+            _ => None,
+        }
     }
 
     pub fn as_f64(&self) -> Result<f64> {
-        self.0.parse().map_err(|e| ConversionError::new(self.clone(), "f64", e))
+        match self.value {
+            TypedValue::Number(n) => Ok(n),
+            _ => self.as_str().parse().map_err(|e| ConversionError::new(self.clone(), "f64", e)),
+        }
     }
 
     pub fn as_i32(&self) -> Result<i32> {
-        self.0.parse().map_err(|e| ConversionError::new(self.clone(), "i32", e))
+        match self.value {
+            TypedValue::Number(n) if n.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(&n) => Ok(n as i32),
+            _ => self.as_str().parse().map_err(|e| ConversionError::new(self.clone(), "i32", e)),
+        }
     }
 
     pub fn as_bool(&self) -> Result<bool> {
-        self.0.parse().map_err(|e| ConversionError::new(self.clone(), "bool", e))
+        match self.value {
+            TypedValue::Bool(b) => Ok(b),
+            _ => self.as_str().parse().map_err(|e| ConversionError::new(self.clone(), "bool", e)),
+        }
+    }
+
+    /// Convert the value into json, keeping its type. Unlike [`Self::as_json_value`], strings are turned into json strings.
+    pub fn to_json(&self) -> serde_json::Value {
+        match &self.value {
+            TypedValue::String(s) => serde_json::Value::String(s.clone()),
+            TypedValue::Bool(b) => serde_json::Value::Bool(*b),
+            TypedValue::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => serde_json::Value::from(*n as i64),
+            TypedValue::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or_else(|| serde_json::Value::String(self.as_str().to_string())),
+            TypedValue::Json(json) => json.clone(),
+        }
+    }
+
+    /// Whether this value is empty or json null, as checked by the `?:` operator.
+    pub fn is_empty_or_null(&self) -> bool {
+        match &self.value {
+            TypedValue::String(s) => s.is_empty() || matches!(serde_json::from_str(s), Ok(serde_json::Value::Null)),
+            TypedValue::Json(json) => json.is_null(),
+            _ => false,
+        }
     }
 
     pub fn as_duration(&self) -> Result<std::time::Duration> {
         use std::time::Duration;
-        let s = &self.0;
+        let s = self.as_str();
         if s.ends_with("ms") {
             Ok(Duration::from_millis(
                 s.trim_end_matches("ms").parse().map_err(|e| ConversionError::new(self.clone(), "integer", e))?,
@@ -201,10 +360,11 @@ impl DynVal {
 
     // TODO this should return Result<Vec<DynVal>> and use json parsing
     pub fn as_vec(&self) -> Result<Vec<String>> {
-        if self.0.is_empty() {
+        let s = self.as_str();
+        if s.is_empty() {
             Ok(Vec::new())
         } else {
-            match self.0.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            match s.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
                 Some(content) => {
                     let mut items: Vec<String> = content.split(',').map(|x: &str| x.to_string()).collect();
                     let mut removed = 0;
@@ -225,25 +385,34 @@ impl DynVal {
         }
     }
 
+    /// The json representation of this value, only parsing it if it isn't already a json array, object or null.
+    pub fn as_json_ref(&self) -> Result<Cow<serde_json::Value>> {
+        match &self.value {
+            TypedValue::Json(json) => Ok(Cow::Borrowed(json)),
+            _ => serde_json::from_str::<serde_json::Value>(self.as_str())
+                .map(Cow::Owned)
+                .map_err(|e| ConversionError::new(self.clone(), "json-value", Box::new(e))),
+        }
+    }
+
     pub fn as_json_value(&self) -> Result<serde_json::Value> {
-        serde_json::from_str::<serde_json::Value>(&self.0)
-            .map_err(|e| ConversionError::new(self.clone(), "json-value", Box::new(e)))
+        self.as_json_ref().map(Cow::into_owned)
     }
 
     pub fn as_json_array(&self) -> Result<Vec<serde_json::Value>> {
-        serde_json::from_str::<serde_json::Value>(&self.0)
-            .map_err(|e| ConversionError::new(self.clone(), "json-value", Box::new(e)))?
-            .as_array()
-            .cloned()
-            .ok_or_else(|| ConversionError { value: self.clone(), target_type: "json-array", source: None })
+        match self.as_json_ref()? {
+            Cow::Borrowed(serde_json::Value::Array(array)) => Ok(array.clone()),
+            Cow::Owned(serde_json::Value::Array(array)) => Ok(array),
+            _ => Err(ConversionError { value: self.clone(), target_type: "json-array", source: None }),
+        }
     }
 
     pub fn as_json_object(&self) -> Result<serde_json::Map<String, serde_json::Value>> {
-        serde_json::from_str::<serde_json::Value>(&self.0)
-            .map_err(|e| ConversionError::new(self.clone(), "json-value", Box::new(e)))?
-            .as_object()
-            .cloned()
-            .ok_or_else(|| ConversionError { value: self.clone(), target_type: "json-object", source: None })
+        match self.as_json_ref()? {
+            Cow::Borrowed(serde_json::Value::Object(object)) => Ok(object.clone()),
+            Cow::Owned(serde_json::Value::Object(object)) => Ok(object),
+            _ => Err(ConversionError { value: self.clone(), target_type: "json-object", source: None }),
+        }
     }
 }
 
@@ -273,4 +442,37 @@ mod test {
         insta::assert_debug_snapshot!(DynVal::from("1h").as_duration());
         insta::assert_debug_snapshot!(DynVal::from("0.5h").as_duration());
     }
+
+    #[test]
+    fn test_typed_values() {
+        assert_eq!(DynVal::from("hi").value(), &TypedValue::String("hi".to_string()));
+        assert_eq!(DynVal::from("1.50").value(), &TypedValue::Number(1.5));
+        assert_eq!(DynVal::from("1.50").to_string(), "1.50");
+        assert_eq!(DynVal::from("true").value(), &TypedValue::Bool(true));
+        assert_eq!(DynVal::from("[1, 2]").value(), &TypedValue::Json(serde_json::json!([1, 2])));
+        assert_eq!(DynVal::from("[1, 2]").to_string(), "[1, 2]");
+        assert_eq!(DynVal::from("[hi,ho]").value(), &TypedValue::String("[hi,ho]".to_string()));
+        assert_eq!(DynVal::from(2.5).to_string(), "2.5");
+        assert_eq!(DynVal::from(3).as_i32().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        assert_eq!(DynVal::from("NaN").value(), &TypedValue::String("NaN".to_string()));
+        assert_eq!(DynVal::from("inf").value(), &TypedValue::String("inf".to_string()));
+        assert_eq!(DynVal::from("NaN"), DynVal::from("NaN"));
+        assert_eq!(DynVal::from(f64::NAN), DynVal::from(f64::NAN));
+        assert_eq!(DynVal::from(f64::INFINITY).to_string(), "inf");
+    }
+
+    #[test]
+    fn test_typed_json_values() {
+        let json = serde_json::json!({ "number": 1, "string": "1", "nested": [true] });
+        assert_eq!(DynVal::from(&json["number"]).value(), &TypedValue::Number(1.0));
+        assert_eq!(DynVal::from(&json["string"]).value(), &TypedValue::String("1".to_string()));
+        assert_eq!(DynVal::from(&json["nested"]).to_string(), "[true]");
+        assert_eq!(DynVal::from(&json["number"]), DynVal::from(&json["string"]));
+        assert_eq!(DynVal::try_from(json.clone()).unwrap().as_json_value().unwrap(), json);
+        assert_eq!(DynVal::try_from(serde_json::json!("hi")).unwrap().to_string(), r#""hi""#);
+    }
 }
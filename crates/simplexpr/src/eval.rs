@@ -43,6 +43,27 @@ pub enum EvalError {
     #[error("Unknown function {0}")]
     UnknownFunction(String),
 
+    #[error("Lambdas can only be used as the last argument of a function call")]
+    UnexpectedLambda,
+
+    #[error("{0} expects a lambda taking {1} argument(s) as its last argument")]
+    ExpectedLambda(String, usize),
+
+    #[error("Expected a single character, but got `{0}`")]
+    ExpectedChar(String),
+
+    #[error("Unknown timezone {0}")]
+    UnknownTimezone(String),
+
+    #[error("Invalid time format {0}")]
+    InvalidTimeFormat(String),
+
+    #[error("Timestamp {0} is out of range")]
+    TimestampOutOfRange(f64),
+
+    #[error("Failed to parse time: {0}")]
+    TimeParseError(#[from] chrono::ParseError),
+
     #[error("Unable to index into value {0}")]
     CannotIndex(String),
 
@@ -91,29 +112,50 @@ impl SimplExpr {
     /// map over all of the variable references, replacing them with whatever expression the provided function returns.
     /// Returns [Err] when the provided function fails with an [Err]
     pub fn try_map_var_refs<E, F: Fn(Span, VarName) -> Result<SimplExpr, E> + Copy>(self, f: F) -> Result<Self, E> {
+        self.try_map_var_refs_dyn(&f)
+    }
+
+    /// Implementation of [`Self::try_map_var_refs`], taking a trait object to allow lambdas to wrap the function.
+    fn try_map_var_refs_dyn<E>(self, f: &dyn Fn(Span, VarName) -> Result<SimplExpr, E>) -> Result<Self, E> {
         use SimplExpr::*;
         Ok(match self {
-            BinOp(span, box a, op, box b) => BinOp(span, Box::new(a.try_map_var_refs(f)?), op, Box::new(b.try_map_var_refs(f)?)),
-            Concat(span, elems) => Concat(span, elems.into_iter().map(|x| x.try_map_var_refs(f)).collect::<Result<_, _>>()?),
-            UnaryOp(span, op, box a) => UnaryOp(span, op, Box::new(a.try_map_var_refs(f)?)),
-            IfElse(span, box a, box b, box c) => {
-                IfElse(span, Box::new(a.try_map_var_refs(f)?), Box::new(b.try_map_var_refs(f)?), Box::new(c.try_map_var_refs(f)?))
+            BinOp(span, box a, op, box b) => {
+                BinOp(span, Box::new(a.try_map_var_refs_dyn(f)?), op, Box::new(b.try_map_var_refs_dyn(f)?))
             }
+            Concat(span, elems) => Concat(span, elems.into_iter().map(|x| x.try_map_var_refs_dyn(f)).collect::<Result<_, _>>()?),
+            UnaryOp(span, op, box a) => UnaryOp(span, op, Box::new(a.try_map_var_refs_dyn(f)?)),
+            IfElse(span, box a, box b, box c) => IfElse(
+                span,
+                Box::new(a.try_map_var_refs_dyn(f)?),
+                Box::new(b.try_map_var_refs_dyn(f)?),
+                Box::new(c.try_map_var_refs_dyn(f)?),
+            ),
             JsonAccess(span, safe, box a, box b) => {
-                JsonAccess(span, safe, Box::new(a.try_map_var_refs(f)?), Box::new(b.try_map_var_refs(f)?))
+                JsonAccess(span, safe, Box::new(a.try_map_var_refs_dyn(f)?), Box::new(b.try_map_var_refs_dyn(f)?))
             }
             FunctionCall(span, name, args) => {
-                FunctionCall(span, name, args.into_iter().map(|x| x.try_map_var_refs(f)).collect::<Result<_, _>>()?)
+                FunctionCall(span, name, args.into_iter().map(|x| x.try_map_var_refs_dyn(f)).collect::<Result<_, _>>()?)
             }
             VarRef(span, name) => f(span, name)?,
+            // Parameters of the lambda shadow any variables of the same name
+            Lambda(span, params, box body) => {
+                let body = body.try_map_var_refs_dyn(&|span: Span, name: VarName| {
+                    if params.contains(&name) {
+                        Ok(VarRef(span, name))
+                    } else {
+                        f(span, name)
+                    }
+                })?;
+                Lambda(span, params, Box::new(body))
+            }
             JsonArray(span, values) => {
-                JsonArray(span, values.into_iter().map(|x| x.try_map_var_refs(f)).collect::<Result<_, _>>()?)
+                JsonArray(span, values.into_iter().map(|x| x.try_map_var_refs_dyn(f)).collect::<Result<_, _>>()?)
             }
             JsonObject(span, entries) => JsonObject(
                 span,
                 entries
                     .into_iter()
-                    .map(|(k, v)| Ok((k.try_map_var_refs(f)?, v.try_map_var_refs(f)?)))
+                    .map(|(k, v)| Ok((k.try_map_var_refs_dyn(f)?, v.try_map_var_refs_dyn(f)?)))
                     .collect::<Result<_, _>>()?,
             ),
             x @ Literal(..) => x,
@@ -162,6 +204,7 @@ impl SimplExpr {
                 refs
             }
             FunctionCall(_, _, args) => args.iter().flat_map(|a| a.var_refs_with_span()).collect(),
+            Lambda(_, params, body) => body.var_refs_with_span().into_iter().filter(|(_, name)| !params.contains(name)).collect(),
             JsonArray(_, values) => values.iter().flat_map(|v| v.var_refs_with_span()).collect(),
             JsonObject(_, entries) => {
                 entries.iter().flat_map(|(k, v)| k.var_refs_with_span().into_iter().chain(v.var_refs_with_span())).collect()
@@ -187,9 +230,9 @@ impl SimplExpr {
                 let mut output = String::new();
                 for elem in elems {
                     let result = elem.eval(values)?;
-                    output.push_str(&result.0);
+                    output.push_str(result.as_str());
                 }
-                Ok(DynVal(output, *span))
+                Ok(DynVal::from_string(output).at(*span))
             }
             SimplExpr::VarRef(span, ref name) => {
                 let similar_ish = values.keys().filter(|keys| strsim::levenshtein(&keys.0, &name.0) < 3).cloned().collect_vec();
@@ -207,8 +250,7 @@ impl SimplExpr {
                     BinOp::And => DynVal::from(a.as_bool()? && b()?.as_bool()?),
                     BinOp::Or => DynVal::from(a.as_bool()? || b()?.as_bool()?),
                     BinOp::Elvis => {
-                        let is_null = matches!(serde_json::from_str(&a.0), Ok(serde_json::Value::Null));
-                        if a.0.is_empty() || is_null {
+                        if a.is_empty_or_null() {
                             b()?
                         } else {
                             a
@@ -262,7 +304,7 @@ impl SimplExpr {
 
                 let is_safe = *safe == AccessType::Safe;
 
-                match val.as_json_value()? {
+                match val.as_json_ref()?.as_ref() {
                     serde_json::Value::Array(val) => {
                         let index = index.as_i32()?;
                         let indexed_value = val.get(index as usize).unwrap_or(&serde_json::Value::Null);
@@ -270,7 +312,7 @@ impl SimplExpr {
                     }
                     serde_json::Value::Object(val) => {
                         let indexed_value = val
-                            .get(&index.as_string()?)
+                            .get(index.as_str())
                             .or_else(|| val.get(&index.as_i32().ok()?.to_string()))
                             .unwrap_or(&serde_json::Value::Null);
                         Ok(DynVal::from(indexed_value).at(*span))
@@ -283,9 +325,16 @@ impl SimplExpr {
                 }
             }
             SimplExpr::FunctionCall(span, function_name, args) => {
+                let (lambda, args) = match args.split_last() {
+                    Some((SimplExpr::Lambda(_, params, body), args)) => {
+                        (Some(Lambda { params, body, values: values.clone() }), args)
+                    }
+                    _ => (None, args.as_slice()),
+                };
                 let args = args.iter().map(|a| a.eval(values)).collect::<Result<_, EvalError>>()?;
-                call_expr_function(function_name, args).map(|x| x.at(*span)).map_err(|e| e.at(*span))
+                call_expr_function(function_name, args, lambda).map(|x| x.at(*span)).map_err(|e| e.at(*span))
             }
+            SimplExpr::Lambda(span, ..) => Err(EvalError::UnexpectedLambda.at(*span)),
             SimplExpr::JsonArray(span, entries) => {
                 let entries = entries
                     .iter()
@@ -305,7 +354,45 @@ impl SimplExpr {
     }
 }
 
-fn call_expr_function(name: &str, args: Vec<DynVal>) -> Result<DynVal, EvalError> {
+/// A lambda given as the last argument of a function call.
+struct Lambda<'a> {
+    params: &'a [VarName],
+    body: &'a SimplExpr,
+    /// The variables available at the function call, extended by the parameters of the lambda
+    values: HashMap<VarName, DynVal>,
+}
+
+impl Lambda<'_> {
+    fn call<const N: usize>(&mut self, args: [DynVal; N]) -> Result<DynVal, EvalError> {
+        for (param, arg) in self.params.iter().zip(args) {
+            self.values.insert(param.clone(), arg);
+        }
+        self.body.eval(&self.values)
+    }
+}
+
+const HIGHER_ORDER_FUNCTIONS: &[&str] = &["map", "filter", "sort_by", "any", "all", "find", "reduce"];
+
+/// Get the lambda given to a higher-order function, making sure it takes the expected number of arguments.
+fn expect_lambda<'a>(name: &str, lambda: Option<Lambda<'a>>, param_count: usize) -> Result<Lambda<'a>, EvalError> {
+    match lambda {
+        Some(lambda) if lambda.params.len() == param_count => Ok(lambda),
+        _ => Err(EvalError::ExpectedLambda(name.to_string(), param_count)),
+    }
+}
+
+/// Order values numerically if both are numbers, and by their text otherwise.
+fn compare_values(a: &DynVal, b: &DynVal) -> std::cmp::Ordering {
+    match (a.parse_f64(), b.parse_f64()) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+        _ => a.as_str().cmp(b.as_str()),
+    }
+}
+
+fn call_expr_function(name: &str, args: Vec<DynVal>, lambda: Option<Lambda>) -> Result<DynVal, EvalError> {
+    if lambda.is_some() && !HIGHER_ORDER_FUNCTIONS.contains(&name) {
+        return Err(EvalError::UnexpectedLambda);
+    }
     match name {
         "round" => match args.as_slice() {
             [num, digits] => {
@@ -360,15 +447,85 @@ fn call_expr_function(name: &str, args: Vec<DynVal>) -> Result<DynVal, EvalError
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
         "strlength" => match args.as_slice() {
-            [string] => Ok(DynVal::from(string.as_string()?.len() as i32)),
+            [string] => Ok(DynVal::from(string.as_str().chars().count() as i32)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "substring" => match args.as_slice() {
+            [string, start] => Ok(DynVal::from(substring(string.as_str(), start.as_i32()?, usize::MAX))),
+            [string, start, length] => Ok(DynVal::from(substring(string.as_str(), start.as_i32()?, char_count(length)?))),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "trim" => match args.as_slice() {
+            [string] => Ok(DynVal::from(string.as_str().trim())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "upper" => match args.as_slice() {
+            [string] => Ok(DynVal::from(string.as_str().to_uppercase())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "lower" => match args.as_slice() {
+            [string] => Ok(DynVal::from(string.as_str().to_lowercase())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "split" => match args.as_slice() {
+            [string, separator] => {
+                use serde_json::Value;
+                let parts: Vec<Value> = match separator.as_str() {
+                    "" => string.as_str().chars().map(|c| Value::String(c.to_string())).collect(),
+                    separator => string.as_str().split(separator).map(|x| Value::String(x.to_string())).collect(),
+                };
+                Ok(DynVal::try_from(Value::Array(parts))?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "join" => match args.as_slice() {
+            [array, separator] => {
+                let array = array.as_json_array()?;
+                Ok(DynVal::from(array.iter().map(|x| DynVal::from(x).into_inner()).join(separator.as_str())))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "padleft" | "padright" => {
+            let (string, length, pad) = match args.as_slice() {
+                [string, length] => (string, length, ' '),
+                [string, length, pad] => (string, length, single_char(pad)?),
+                _ => return Err(EvalError::WrongArgCount(name.to_string())),
+            };
+            let string = string.as_str();
+            let padding = pad.to_string().repeat(char_count(length)?.saturating_sub(string.chars().count()));
+            if name == "padleft" {
+                Ok(DynVal::from(padding + string))
+            } else {
+                Ok(DynVal::from(string.to_string() + &padding))
+            }
+        }
+        "startswith" => match args.as_slice() {
+            [string, prefix] => Ok(DynVal::from(string.as_str().starts_with(prefix.as_str()))),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "endswith" => match args.as_slice() {
+            [string, suffix] => Ok(DynVal::from(string.as_str().ends_with(suffix.as_str()))),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "truncate" => match args.as_slice() {
+            [string, max_length] => Ok(DynVal::from(truncate(string.as_str(), char_count(max_length)?, "..."))),
+            [string, max_length, ellipsis] => {
+                Ok(DynVal::from(truncate(string.as_str(), char_count(max_length)?, ellipsis.as_str())))
+            }
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
         "arraylength" => match args.as_slice() {
-            [json] => Ok(DynVal::from(json.as_json_array()?.len() as i32)),
+            [json] => match json.as_json_ref()?.as_ref() {
+                serde_json::Value::Array(array) => Ok(DynVal::from(array.len() as i32)),
+                _ => Err(ConversionError { value: json.clone(), target_type: "json-array", source: None }.into()),
+            },
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
         "objectlength" => match args.as_slice() {
-            [json] => Ok(DynVal::from(json.as_json_object()?.len() as i32)),
+            [json] => match json.as_json_ref()?.as_ref() {
+                serde_json::Value::Object(object) => Ok(DynVal::from(object.len() as i32)),
+                _ => Err(ConversionError { value: json.clone(), target_type: "json-object", source: None }.into()),
+            },
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
         "jq" => match args.as_slice() {
@@ -376,11 +533,183 @@ fn call_expr_function(name: &str, args: Vec<DynVal>) -> Result<DynVal, EvalError
                 .map_err(|e| EvalError::Spanned(code.span(), Box::new(e))),
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
+        "now" => match args.as_slice() {
+            [] => Ok(DynVal::from(chrono::Utc::now().timestamp())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "formattime" => match args.as_slice() {
+            [timestamp, format] => Ok(DynVal::from(format_time(timestamp.as_f64()?, format.as_str(), &chrono::Local)?)),
+            [timestamp, format, timezone] => {
+                let timezone = parse_timezone(timezone)?;
+                Ok(DynVal::from(format_time(timestamp.as_f64()?, format.as_str(), &timezone)?))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "parsetime" => match args.as_slice() {
+            [time, format] => Ok(DynVal::from(parse_time(time.as_str(), format.as_str(), &chrono::Local)?)),
+            [time, format, timezone] => {
+                let timezone = parse_timezone(timezone)?;
+                Ok(DynVal::from(parse_time(time.as_str(), format.as_str(), &timezone)?))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "map" => match args.as_slice() {
+            [array] => {
+                let mut f = expect_lambda(name, lambda, 1)?;
+                let mapped = array.as_json_array()?.iter().map(|x| Ok(f.call([DynVal::from(x)])?.to_json())).collect::<Result<
+                    _,
+                    EvalError,
+                >>(
+                )?;
+                Ok(DynVal::try_from(serde_json::Value::Array(mapped))?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "filter" => match args.as_slice() {
+            [array] => {
+                let mut f = expect_lambda(name, lambda, 1)?;
+                let mut filtered = Vec::new();
+                for x in array.as_json_array()? {
+                    if f.call([DynVal::from(&x)])?.as_bool()? {
+                        filtered.push(x);
+                    }
+                }
+                Ok(DynVal::try_from(serde_json::Value::Array(filtered))?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "sort_by" => match args.as_slice() {
+            [array] => {
+                let mut f = expect_lambda(name, lambda, 1)?;
+                let mut keyed = array
+                    .as_json_array()?
+                    .into_iter()
+                    .map(|x| Ok((f.call([DynVal::from(&x)])?, x)))
+                    .collect::<Result<Vec<_>, EvalError>>()?;
+                keyed.sort_by(|(a, _), (b, _)| compare_values(a, b));
+                Ok(DynVal::try_from(serde_json::Value::Array(keyed.into_iter().map(|(_, x)| x).collect()))?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "any" | "all" => match args.as_slice() {
+            [array] => {
+                let mut f = expect_lambda(name, lambda, 1)?;
+                // `any` stops at the first match, `all` at the first mismatch
+                let stop_at = name == "any";
+                for x in array.as_json_array()? {
+                    if f.call([DynVal::from(&x)])?.as_bool()? == stop_at {
+                        return Ok(DynVal::from(stop_at));
+                    }
+                }
+                Ok(DynVal::from(!stop_at))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "find" => match args.as_slice() {
+            [array] => {
+                let mut f = expect_lambda(name, lambda, 1)?;
+                for x in array.as_json_array()? {
+                    if f.call([DynVal::from(&x)])?.as_bool()? {
+                        return Ok(DynVal::from(&x));
+                    }
+                }
+                Ok(DynVal::from(&serde_json::Value::Null))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "reduce" => match args.as_slice() {
+            [array, initial] => {
+                let mut f = expect_lambda(name, lambda, 2)?;
+                let mut acc = initial.clone();
+                for x in array.as_json_array()? {
+                    acc = f.call([acc, DynVal::from(&x)])?;
+                }
+                Ok(acc)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
 
         _ => Err(EvalError::UnknownFunction(name.to_string())),
     }
 }
 
+/// Read a number of characters, treating negative numbers as zero.
+fn char_count(value: &DynVal) -> Result<usize, EvalError> {
+    Ok(value.as_i32()?.max(0) as usize)
+}
+
+fn single_char(value: &DynVal) -> Result<char, EvalError> {
+    let mut chars = value.as_str().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(EvalError::ExpectedChar(value.to_string())),
+    }
+}
+
+/// Get up to `length` characters of a string, starting at the character at `start`.
+/// A negative `start` counts from the end of the string.
+fn substring(string: &str, start: i32, length: usize) -> String {
+    let start = if start < 0 { string.chars().count().saturating_sub(start.unsigned_abs() as usize) } else { start as usize };
+    string.chars().skip(start).take(length).collect()
+}
+
+/// Shorten a string to at most `max_length` characters, ending it with the ellipsis if it had to be shortened.
+fn truncate(string: &str, max_length: usize, ellipsis: &str) -> String {
+    if string.chars().count() <= max_length {
+        return string.to_string();
+    }
+    let ellipsis_length = ellipsis.chars().count();
+    if ellipsis_length >= max_length {
+        return string.chars().take(max_length).collect();
+    }
+    string.chars().take(max_length - ellipsis_length).chain(ellipsis.chars()).collect()
+}
+
+fn parse_timezone(timezone: &DynVal) -> Result<chrono_tz::Tz, EvalError> {
+    timezone.as_str().parse().map_err(|_| EvalError::UnknownTimezone(timezone.to_string()))
+}
+
+/// Get the strftime items of a format string, failing on invalid specifiers instead of panicking when formatting.
+fn strftime_items(format: &str) -> Result<chrono::format::StrftimeItems<'_>, EvalError> {
+    let items = chrono::format::StrftimeItems::new(format);
+    if items.clone().any(|item| item == chrono::format::Item::Error) {
+        return Err(EvalError::InvalidTimeFormat(format.to_string()));
+    }
+    Ok(items)
+}
+
+/// Format a unix timestamp in seconds using a strftime-style format.
+fn format_time<Tz: chrono::TimeZone>(timestamp: f64, format: &str, timezone: &Tz) -> Result<String, EvalError>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let items = strftime_items(format)?;
+    let nanos = ((timestamp - timestamp.floor()) * 1e9) as u32;
+    let datetime = chrono::NaiveDateTime::from_timestamp_opt(timestamp.floor() as i64, nanos)
+        .ok_or(EvalError::TimestampOutOfRange(timestamp))?;
+    Ok(timezone.from_utc_datetime(&datetime).format_with_items(items).to_string())
+}
+
+/// Parse a time using a strftime-style format, returning the unix timestamp in seconds.
+/// Times without an offset are interpreted in the given timezone, dates without a time at midnight.
+fn parse_time<Tz: chrono::TimeZone>(time: &str, format: &str, timezone: &Tz) -> Result<i64, EvalError> {
+    strftime_items(format)?;
+    if let Ok(datetime) = chrono::DateTime::parse_from_str(time, format) {
+        return Ok(datetime.timestamp());
+    }
+    let datetime = match chrono::NaiveDateTime::parse_from_str(time, format) {
+        Ok(datetime) => datetime,
+        Err(err) => match chrono::NaiveDate::parse_from_str(time, format) {
+            Ok(date) => date.and_hms_opt(0, 0, 0).ok_or(err)?,
+            Err(_) => return Err(err.into()),
+        },
+    };
+    match timezone.from_local_datetime(&datetime).earliest() {
+        Some(datetime) => Ok(datetime.timestamp()),
+        None => Err(EvalError::InvalidTimeFormat(format.to_string())),
+    }
+}
+
 #[cached(size = 10, result = true, sync_writes = true)]
 fn prepare_jaq_filter(code: String) -> Result<Arc<jaq_core::Filter>, EvalError> {
     let (filter, mut errors) = jaq_core::parse::parse(&code, jaq_core::parse::main());
@@ -468,5 +797,52 @@ mod tests {
         lazy_evaluation_and(r#"false && "null".test"#) => Ok(DynVal::from(false)),
         lazy_evaluation_or(r#"true || "null".test"#) => Ok(DynVal::from(true)),
         lazy_evaluation_elvis(r#""test"?: "null".test"#) => Ok(DynVal::from("test")),
+        map_array(r#"map("[1, 2, 3]", x => x * 2)"#) => Ok(DynVal::from("[2, 4, 6]")),
+        map_array_to_field(r#"map("[{\"name\": \"a\"}, {\"name\": \"1\"}]", x => x.name)"#) => Ok(DynVal::from(r#"["a", "1"]"#)),
+        filter_array(r#"filter("[1, 2, 3, 4]", x => x % 2 == 0)"#) => Ok(DynVal::from("[2, 4]")),
+        sort_array_by(r#"sort_by("[2, 10, 1]", x => -x)"#) => Ok(DynVal::from("[10, 2, 1]")),
+        any_in_array(r#"any("[1, 2]", x => x > 1)"#) => Ok(DynVal::from(true)),
+        all_in_array(r#"all("[1, 2]", x => x > 1)"#) => Ok(DynVal::from(false)),
+        find_in_array(r#"find("[1, 2, 3]", x => x > 1)"#) => Ok(DynVal::from(2)),
+        find_missing_in_array(r#"find("[1, 2, 3]", x => x > 5)"#) => Ok(DynVal::from(&serde_json::Value::Null)),
+        reduce_array(r#"reduce("[1, 2, 3]", 0, (acc, x) => acc + x)"#) => Ok(DynVal::from(6)),
+        now_is_current(r#"now() > parsetime("2024-01-01", "%Y-%m-%d", "UTC")"#) => Ok(DynVal::from(true)),
+        now_takes_no_args("now(1)") => Err(super::EvalError::WrongArgCount("now".to_string())),
+        formattime_in_timezone(r#"formattime(0, "%Y-%m-%d %H:%M", "Europe/Berlin")"#) => Ok(DynVal::from("1970-01-01 01:00")),
+        formattime_fractional(r#"formattime(90.5, "%M:%S%.3f", "UTC")"#) => Ok(DynVal::from("01:30.500")),
+        formattime_invalid_format(r#"formattime(0, "%Q", "UTC")"#) => Err(super::EvalError::InvalidTimeFormat("%Q".to_string())),
+        formattime_unknown_timezone(r#"formattime(0, "%H", "X")"#) => Err(super::EvalError::UnknownTimezone("X".to_string())),
+        parsetime_in_timezone(r#"parsetime("1970-01-02 00:00", "%Y-%m-%d %H:%M", "UTC")"#) => Ok(DynVal::from(86400)),
+        parsetime_date(r#"parsetime("1970-01-02", "%Y-%m-%d", "Europe/Berlin")"#) => Ok(DynVal::from(82800)),
+        parsetime_with_offset(r#"parsetime("2023-01-01 00:00 +0100", "%Y-%m-%d %H:%M %z")"#) => Ok(DynVal::from(1672527600)),
+        strlength_counts_chars(r#"strlength("héllo")"#) => Ok(DynVal::from(5)),
+        substring_with_length(r#"substring("héllo wörld", 1, 4)"#) => Ok(DynVal::from("éllo")),
+        substring_from_end(r#"substring("héllo", -2)"#) => Ok(DynVal::from("lo")),
+        substring_out_of_range(r#"substring("héllo", 10, 2)"#) => Ok(DynVal::from("")),
+        trim_string(r#"trim("  hi  ")"#) => Ok(DynVal::from("hi")),
+        upper_unicode(r#"upper("straße")"#) => Ok(DynVal::from("STRASSE")),
+        lower_unicode(r#"lower("ÀÉ")"#) => Ok(DynVal::from("àé")),
+        split_string(r#"split("a, b, c", ", ")"#) => Ok(DynVal::from(r#"["a", "b", "c"]"#)),
+        split_into_chars(r#"split("äb", "")"#) => Ok(DynVal::from(r#"["ä", "b"]"#)),
+        join_array(r#"join("[1, \"a\", true]", "-")"#) => Ok(DynVal::from("1-a-true")),
+        padleft_string(r#"padleft("ab", 4)"#) => Ok(DynVal::from("  ab")),
+        padright_unicode(r#"padright("é", 3, "·")"#) => Ok(DynVal::from("é··")),
+        pad_already_long(r#"padleft("hello", 2, "0")"#) => Ok(DynVal::from("hello")),
+        pad_with_string(r#"padleft("a", 3, "ab")"#) => Err(super::EvalError::ExpectedChar("ab".to_string())),
+        startswith_string(r#"startswith("héllo", "hé")"#) => Ok(DynVal::from(true)),
+        endswith_string(r#"endswith("héllo", "hé")"#) => Ok(DynVal::from(false)),
+        truncate_string(r#"truncate("héllo wörld", 6)"#) => Ok(DynVal::from("hél...")),
+        truncate_short_string(r#"truncate("hi", 6)"#) => Ok(DynVal::from("hi")),
+        truncate_with_ellipsis(r#"truncate("héllo", 3, "…")"#) => Ok(DynVal::from("hé…")),
+        lambda_outside_function(r#"x => x"#) => Err(super::EvalError::UnexpectedLambda),
+        lambda_with_wrong_arg_count(r#"map("[1]", (a, b) => a)"#) => Err(super::EvalError::ExpectedLambda("map".to_string(), 1)),
+    }
+
+    #[test]
+    fn lambda_params_shadow_variables() {
+        let expr = crate::parser::parse_string(0, 0, "map(x, x => x + 1)").unwrap();
+        assert_eq!(expr.var_refs_with_span().len(), 1);
+        let values = std::collections::HashMap::from([(eww_shared_util::VarName::from("x"), DynVal::from("[1, 2]"))]);
+        assert_eq!(expr.resolve_refs(&values).unwrap().eval_no_vars().unwrap(), DynVal::from("[2, 3]"));
     }
 }
@@ -30,6 +30,7 @@ pub enum Token {
     Elvis,
     SafeAccess,
     RegexMatch,
+    Arrow,
 
     Not,
     Negative,
@@ -91,6 +92,7 @@ regex_rules! {
     r"\?:"    => |_| Token::Elvis,
     r"\?\."    => |_| Token::SafeAccess,
     r"=~"    => |_| Token::RegexMatch,
+    r"=>"    => |_| Token::Arrow,
 
     r"!"     => |_| Token::Not,
     r"-"     => |_| Token::Negative,
@@ -0,0 +1,57 @@
+use simplexpr::{dynval::DynVal, SimplExpr};
+
+use crate::{
+    error::{DiagError, DiagResult, DiagResultExt},
+    format_diagnostic::ToDiagnostic,
+    parser::{ast::Ast, ast_iterator::AstIterator, from_ast::FromAstElementContent},
+};
+use eww_shared_util::{Span, VarName};
+
+/// Settings for one of the inbuilt magic variables, overriding how often and while which condition it is updated.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct MagicVarDefinition {
+    pub name: VarName,
+    pub interval: Option<std::time::Duration>,
+    pub run_while_expr: Option<SimplExpr>,
+    pub name_span: Span,
+}
+
+impl FromAstElementContent for MagicVarDefinition {
+    const ELEMENT_NAME: &'static str = "defmagic";
+
+    fn from_tail<I: Iterator<Item = Ast>>(_span: Span, mut iter: AstIterator<I>) -> DiagResult<Self> {
+        let result: DiagResult<_> = try {
+            let (name_span, name) = iter.expect_symbol()?;
+            let mut attrs = iter.expect_key_values()?;
+            let interval = attrs
+                .primitive_optional::<DynVal, _>("interval")?
+                .map(|interval| interval.as_duration())
+                .transpose()
+                .map_err(|e| DiagError(e.to_diagnostic()))?;
+            let run_while_expr = attrs.ast_optional::<SimplExpr>("run-while")?;
+            iter.expect_done()?;
+            Self { name: VarName(name), interval, run_while_expr, name_span }
+        };
+        result.note(r#"Expected format: `(defmagic EWW_CPU :interval "500ms" :run-while cpu-visible)`"#)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{self, from_ast::FromAst};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_magic_var_definition() {
+        let parse = |code| MagicVarDefinition::from_ast(parser::parse_string(0, code).unwrap());
+        let def = parse(r#"(defmagic EWW_CPU :interval "500ms" :run-while visible)"#).unwrap();
+        assert_eq!(def.name, VarName::from("EWW_CPU"));
+        assert_eq!(def.interval, Some(Duration::from_millis(500)));
+        assert_eq!(def.run_while_expr.unwrap().collect_var_refs(), vec![VarName::from("visible")]);
+        let def = parse(r#"(defmagic EWW_BATTERY :interval "30s")"#).unwrap();
+        assert_eq!(def.interval, Some(Duration::from_secs(30)));
+        assert!(def.run_while_expr.is_none());
+        assert!(parse(r#"(defmagic EWW_CPU :interval "soon")"#).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod attributes;
 pub mod backend_window_options;
 pub mod file_provider;
+pub mod magic_var_definition;
 pub mod monitor;
 pub mod script_var_definition;
 pub mod toplevel;
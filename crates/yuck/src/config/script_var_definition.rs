@@ -28,6 +28,27 @@ impl ScriptVarDefinition {
         }
     }
 
+    /// Check if both definitions describe the same variable, ignoring where in the configuration they are defined.
+    pub fn is_same_definition(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ScriptVarDefinition::Poll(a), ScriptVarDefinition::Poll(b)) => {
+                let same_command = match (&a.command, &b.command) {
+                    (VarSource::Shell(_, a), VarSource::Shell(_, b)) => a == b,
+                    (a, b) => a == b,
+                };
+                same_command
+                    && a.name == b.name
+                    && a.interval == b.interval
+                    && a.initial_value == b.initial_value
+                    && a.run_while_expr.to_string() == b.run_while_expr.to_string()
+            }
+            (ScriptVarDefinition::Listen(a), ScriptVarDefinition::Listen(b)) => {
+                a.name == b.name && a.command == b.command && a.initial_value == b.initial_value
+            }
+            _ => false,
+        }
+    }
+
     pub fn command_span(&self) -> Option<Span> {
         match self {
             ScriptVarDefinition::Poll(x) => match x.command {
@@ -109,3 +130,22 @@ impl FromAstElementContent for ListenScriptVar {
         result.note(r#"Expected format: `(deflisten name :initial "0" "tail -f /tmp/example")`"#)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{self, from_ast::FromAst};
+
+    fn parse_poll(code: &str) -> ScriptVarDefinition {
+        ScriptVarDefinition::Poll(PollScriptVar::from_ast(parser::parse_string(0, code).unwrap()).unwrap())
+    }
+
+    #[test]
+    fn test_is_same_definition() {
+        let original = parse_poll(r#"(defpoll foo :interval "1s" "date")"#);
+        let moved = parse_poll(r#"(defpoll   foo   :interval "1s"   "date")"#);
+        let changed = parse_poll(r#"(defpoll foo :interval "2s" "date")"#);
+        assert!(original.is_same_definition(&moved));
+        assert!(!original.is_same_definition(&changed));
+    }
+}
@@ -7,6 +7,7 @@ use itertools::Itertools;
 
 use super::{
     file_provider::{FilesError, YuckFileProvider},
+    magic_var_definition::MagicVarDefinition,
     script_var_definition::ScriptVarDefinition,
     var_definition::VarDefinition,
     widget_definition::WidgetDefinition,
@@ -14,7 +15,7 @@ use super::{
 };
 use crate::{
     config::script_var_definition::{ListenScriptVar, PollScriptVar},
-    error::{DiagError, DiagResult},
+    error::{DiagError, DiagErrors, DiagResult},
     gen_diagnostic,
     parser::{
         ast::Ast,
@@ -30,6 +31,7 @@ static TOP_LEVEL_DEFINITION_NAMES: &[&str] = &[
     VarDefinition::ELEMENT_NAME,
     ListenScriptVar::ELEMENT_NAME,
     PollScriptVar::ELEMENT_NAME,
+    MagicVarDefinition::ELEMENT_NAME,
     Include::ELEMENT_NAME,
 ];
 
@@ -53,6 +55,7 @@ pub enum TopLevel {
     Include(Include),
     VarDefinition(VarDefinition),
     ScriptVarDefinition(ScriptVarDefinition),
+    MagicVarDefinition(MagicVarDefinition),
     WidgetDefinition(WidgetDefinition),
     WindowDefinition(WindowDefinition),
 }
@@ -72,6 +75,7 @@ impl FromAst for TopLevel {
             x if x == ListenScriptVar::ELEMENT_NAME => {
                 Self::ScriptVarDefinition(ScriptVarDefinition::Listen(ListenScriptVar::from_tail(span, iter)?))
             }
+            x if x == MagicVarDefinition::ELEMENT_NAME => Self::MagicVarDefinition(MagicVarDefinition::from_tail(span, iter)?),
             x if x == WindowDefinition::ELEMENT_NAME => Self::WindowDefinition(WindowDefinition::from_tail(span, iter)?),
             x => {
                 return Err(DiagError(gen_diagnostic! {
@@ -90,14 +94,18 @@ pub struct Config {
     pub window_definitions: HashMap<String, WindowDefinition>,
     pub var_definitions: HashMap<VarName, VarDefinition>,
     pub script_vars: HashMap<VarName, ScriptVarDefinition>,
+    /// Settings for inbuilt magic variables, defined using `defmagic`
+    pub magic_var_definitions: HashMap<VarName, MagicVarDefinition>,
 }
 
 impl Config {
-    fn append_toplevel(&mut self, files: &mut impl YuckFileProvider, toplevel: TopLevel) -> DiagResult<()> {
+    /// Add a toplevel declaration to the config.
+    /// Recoverable errors, like variables that are defined twice, are added to `errors`, skipping only the affected declaration.
+    fn append_toplevel(&mut self, files: &mut impl YuckFileProvider, toplevel: TopLevel, errors: &mut Vec<DiagError>) {
         match toplevel {
             TopLevel::VarDefinition(x) => {
                 if self.var_definitions.contains_key(&x.name) || self.script_vars.contains_key(&x.name) {
-                    return Err(DiagError(gen_diagnostic! {
+                    errors.push(DiagError(gen_diagnostic! {
                         msg = format!("Variable {} defined twice", x.name),
                         label = x.span => "defined again here",
                     }));
@@ -107,7 +115,7 @@ impl Config {
             }
             TopLevel::ScriptVarDefinition(x) => {
                 if self.var_definitions.contains_key(x.name()) || self.script_vars.contains_key(x.name()) {
-                    return Err(DiagError(gen_diagnostic! {
+                    errors.push(DiagError(gen_diagnostic! {
                         msg = format!("Variable {} defined twice", x.name()),
                         label = x.name_span() => "defined again here",
                     }));
@@ -115,42 +123,58 @@ impl Config {
                     self.script_vars.insert(x.name().clone(), x);
                 }
             }
+            TopLevel::MagicVarDefinition(x) => {
+                if self.magic_var_definitions.contains_key(&x.name) {
+                    errors.push(DiagError(gen_diagnostic! {
+                        msg = format!("Magic variable {} configured twice", x.name),
+                        label = x.name_span => "configured again here",
+                    }));
+                } else {
+                    self.magic_var_definitions.insert(x.name.clone(), x);
+                }
+            }
             TopLevel::WidgetDefinition(x) => {
                 self.widget_definitions.insert(x.name.clone(), x);
             }
             TopLevel::WindowDefinition(x) => {
                 self.window_definitions.insert(x.name.clone(), x);
             }
-            TopLevel::Include(include) => {
-                let (_, toplevels) = files.load_yuck_file(PathBuf::from(&include.path)).map_err(|err| match err {
-                    FilesError::IoError(_) => DiagError(gen_diagnostic! {
-                        msg = format!("Included file `{}` not found", include.path),
-                        label = include.path_span => "Included here",
-                    }),
-                    FilesError::DiagError(x) => x,
-                })?;
-                for element in toplevels {
-                    self.append_toplevel(files, TopLevel::from_ast(element)?)?;
-                }
+            TopLevel::Include(include) => match files.load_yuck_file(PathBuf::from(&include.path)) {
+                Ok((_, toplevels)) => self.append_toplevels(files, toplevels, errors),
+                Err(FilesError::IoError(_)) => errors.push(DiagError(gen_diagnostic! {
+                    msg = format!("Included file `{}` not found", include.path),
+                    label = include.path_span => "Included here",
+                })),
+                Err(FilesError::DiagError(err)) => errors.push(err),
+            },
+        }
+    }
+
+    fn append_toplevels(&mut self, files: &mut impl YuckFileProvider, elements: Vec<Ast>, errors: &mut Vec<DiagError>) {
+        for element in elements {
+            match TopLevel::from_ast(element) {
+                Ok(toplevel) => self.append_toplevel(files, toplevel, errors),
+                Err(err) => errors.push(err),
             }
         }
-        Ok(())
     }
 
-    pub fn generate(files: &mut impl YuckFileProvider, elements: Vec<Ast>) -> DiagResult<Self> {
+    /// Generate the config from the given toplevel declarations, reporting the errors of all invalid declarations.
+    pub fn generate(files: &mut impl YuckFileProvider, elements: Vec<Ast>) -> Result<Self, DiagErrors> {
         let mut config = Self {
             widget_definitions: HashMap::new(),
             window_definitions: HashMap::new(),
             var_definitions: HashMap::new(),
             script_vars: HashMap::new(),
+            magic_var_definitions: HashMap::new(),
         };
-        for element in elements {
-            config.append_toplevel(files, TopLevel::from_ast(element)?)?;
-        }
+        let mut errors = Vec::new();
+        config.append_toplevels(files, elements, &mut errors);
+        DiagErrors::from_errors(errors)?;
         Ok(config)
     }
 
-    pub fn generate_from_main_file(files: &mut impl YuckFileProvider, path: impl AsRef<Path>) -> DiagResult<Self> {
+    pub fn generate_from_main_file(files: &mut impl YuckFileProvider, path: impl AsRef<Path>) -> Result<Self, DiagErrors> {
         let (_span, top_levels) = files.load_yuck_file(path.as_ref().to_path_buf()).map_err(|err| match err {
             FilesError::IoError(err) => DiagError(gen_diagnostic!(err)),
             FilesError::DiagError(x) => x,
@@ -2,7 +2,11 @@ use std::collections::{HashMap, HashSet};
 
 use simplexpr::SimplExpr;
 
-use super::{widget_definition::WidgetDefinition, widget_use::WidgetUse, Config};
+use super::{
+    magic_var_definition::MagicVarDefinition, widget_definition::WidgetDefinition, widget_use::WidgetUse,
+    window_definition::WindowDefinition, Config,
+};
+use crate::error::{DiagError, DiagErrors};
 use eww_shared_util::{AttrName, Span, Spanned, VarName};
 
 #[derive(Debug, thiserror::Error)]
@@ -13,6 +17,9 @@ pub enum ValidationError {
     #[error("Missing attribute `{arg_name}` in use of widget `{widget_name}`")]
     MissingAttr { widget_name: String, arg_name: AttrName, arg_list_span: Option<Span>, use_span: Span },
 
+    #[error("Unknown attribute `{attr_name}` in use of widget `{widget_name}`")]
+    UnknownAttr { widget_name: String, attr_name: AttrName, arg_list_span: Span, attr_span: Span },
+
     #[error("No variable named `{name}` in scope")]
     UnknownVariable {
         span: Span,
@@ -20,38 +27,56 @@ pub enum ValidationError {
         /// True if the error occurred inside a widget definition, false if it occurred in a window definition
         in_definition: bool,
     },
+
+    #[error("`monitor` and `geometry` can only use the arguments of the window, but `{name}` is not one of them")]
+    NonArgumentInWindowProperty { span: Span, name: VarName },
+
+    #[error("No global variable named `{name}` exists")]
+    UnknownVariableInMagicVar { span: Span, name: VarName },
 }
 
 impl Spanned for ValidationError {
     fn span(&self) -> Span {
         match self {
             ValidationError::MissingAttr { use_span, .. } => *use_span,
+            ValidationError::UnknownAttr { attr_span, .. } => *attr_span,
             ValidationError::UnknownVariable { span, .. } => *span,
+            ValidationError::NonArgumentInWindowProperty { span, .. } => *span,
+            ValidationError::UnknownVariableInMagicVar { span, .. } => *span,
             ValidationError::AccidentalBuiltinOverride(span, ..) => *span,
         }
     }
 }
 
-pub fn validate(config: &Config, additional_globals: Vec<VarName>) -> Result<(), ValidationError> {
+/// Validate the whole configuration, reporting all errors that were found.
+pub fn validate(config: &Config, additional_globals: Vec<VarName>) -> Result<(), DiagErrors> {
     let var_names = std::iter::empty()
         .chain(additional_globals.iter().cloned())
         .chain(config.script_vars.keys().cloned())
         .chain(config.var_definitions.keys().cloned())
         .collect();
+    let mut errors = Vec::new();
     for window in config.window_definitions.values() {
-        validate_variables_in_widget_use(&config.widget_definitions, &var_names, &window.widget, false)?;
+        errors.extend(validate_window_definition(&config.widget_definitions, &var_names, window));
     }
     for def in config.widget_definitions.values() {
-        validate_widget_definition(&config.widget_definitions, &var_names, def)?;
+        errors.extend(validate_widget_definition(&config.widget_definitions, &var_names, def));
+    }
+    for def in config.magic_var_definitions.values() {
+        errors.extend(validate_magic_var_definition(&var_names, def));
     }
-    Ok(())
+    errors.sort_by_key(|err| {
+        let span = err.span();
+        (span.2, span.0)
+    });
+    DiagErrors::from_errors(errors.into_iter().map(DiagError::from).collect())
 }
 
 pub fn validate_widget_definition(
     other_defs: &HashMap<String, WidgetDefinition>,
     globals: &HashSet<VarName>,
     def: &WidgetDefinition,
-) -> Result<(), ValidationError> {
+) -> Vec<ValidationError> {
     let mut variables_in_scope = globals.clone();
     for arg in def.expected_args.iter() {
         variables_in_scope.insert(VarName(arg.name.to_string()));
@@ -60,58 +85,153 @@ pub fn validate_widget_definition(
     validate_variables_in_widget_use(other_defs, &variables_in_scope, &def.widget, true)
 }
 
+/// Validate a window definition. The widget of the window may use globals and the arguments of the window,
+/// while `monitor` and `geometry` are evaluated when opening the window, and may thus only use the arguments.
+pub fn validate_window_definition(
+    widget_defs: &HashMap<String, WidgetDefinition>,
+    globals: &HashSet<VarName>,
+    def: &WindowDefinition,
+) -> Vec<ValidationError> {
+    let args: HashSet<VarName> = def.expected_args.iter().map(|arg| VarName(arg.name.to_string())).collect();
+
+    let mut var_refs = def.monitor.iter().flat_map(|expr| expr.var_refs_with_span()).collect::<Vec<_>>();
+    var_refs.extend(def.geometry.iter().flat_map(|geometry| geometry.var_refs()));
+    let mut errors: Vec<_> = var_refs
+        .into_iter()
+        .filter(|(_, var)| !args.contains(*var))
+        .map(|(span, var)| ValidationError::NonArgumentInWindowProperty { span, name: var.clone() })
+        .collect();
+
+    let variables_in_scope = globals.union(&args).cloned().collect();
+    errors.extend(validate_variables_in_widget_use(widget_defs, &variables_in_scope, &def.widget, false));
+    errors
+}
+
+/// Validate the `run-while` condition of a `defmagic`, which may only refer to global variables.
+pub fn validate_magic_var_definition(globals: &HashSet<VarName>, def: &MagicVarDefinition) -> Vec<ValidationError> {
+    def.run_while_expr
+        .iter()
+        .flat_map(|expr| expr.var_refs_with_span())
+        .filter(|(_, var)| !globals.contains(*var))
+        .map(|(span, var)| ValidationError::UnknownVariableInMagicVar { span, name: var.clone() })
+        .collect()
+}
+
+/// Find all missing or unknown attributes and references to unknown variables in the given widget use and its children.
 pub fn validate_variables_in_widget_use(
     defs: &HashMap<String, WidgetDefinition>,
     variables: &HashSet<VarName>,
     widget: &WidgetUse,
     is_in_definition: bool,
-) -> Result<(), ValidationError> {
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let unknown_variables = |expr: &SimplExpr, allowed: Option<&VarName>| {
+        expr.var_refs_with_span()
+            .into_iter()
+            .filter(|(_, var_ref)| Some(*var_ref) != allowed && !variables.contains(*var_ref))
+            .map(|(span, var_ref)| ValidationError::UnknownVariable {
+                span,
+                name: var_ref.clone(),
+                in_definition: is_in_definition,
+            })
+            .collect::<Vec<_>>()
+    };
     if let WidgetUse::Basic(widget) = widget {
-        let matching_definition = defs.get(&widget.name);
-        if let Some(matching_def) = matching_definition {
-            let missing_arg = matching_def
+        if let Some(matching_def) = defs.get(&widget.name) {
+            let missing_args = matching_def
                 .expected_args
                 .iter()
-                .find(|expected| !expected.optional && !widget.attrs.attrs.contains_key(&expected.name));
-            if let Some(missing_arg) = missing_arg {
-                return Err(ValidationError::MissingAttr {
-                    widget_name: widget.name.clone(),
-                    arg_name: missing_arg.name.clone(),
-                    arg_list_span: Some(matching_def.args_span),
-                    use_span: widget.attrs.span,
-                });
-            }
-        }
-        let values = widget.attrs.attrs.values();
-        let unknown_var = values.filter_map(|value| value.value.as_simplexpr().ok()).find_map(|expr: SimplExpr| {
-            expr.var_refs_with_span()
+                .filter(|expected| !expected.optional && !widget.attrs.attrs.contains_key(&expected.name));
+            errors.extend(missing_args.map(|missing_arg| ValidationError::MissingAttr {
+                widget_name: widget.name.clone(),
+                arg_name: missing_arg.name.clone(),
+                arg_list_span: Some(matching_def.args_span),
+                use_span: widget.attrs.span,
+            }));
+            let unknown_attrs = widget
+                .attrs
+                .attrs
                 .iter()
-                .cloned()
-                .map(|(span, var_ref)| (span, var_ref.clone()))
-                .find(|(_, var_ref)| !variables.contains(var_ref))
-        });
-        if let Some((span, var)) = unknown_var {
-            return Err(ValidationError::UnknownVariable { span, name: var, in_definition: is_in_definition });
+                .filter(|(name, _)| !matching_def.expected_args.iter().any(|expected| &expected.name == *name));
+            errors.extend(unknown_attrs.map(|(name, entry)| ValidationError::UnknownAttr {
+                widget_name: widget.name.clone(),
+                attr_name: name.clone(),
+                arg_list_span: matching_def.args_span,
+                attr_span: entry.key_span,
+            }));
+        }
+        for value in widget.attrs.attrs.values() {
+            if let Ok(expr) = value.value.as_simplexpr() {
+                errors.extend(unknown_variables(&expr, None));
+            }
         }
 
         for child in widget.children.iter() {
-            validate_variables_in_widget_use(defs, variables, child, is_in_definition)?;
+            errors.extend(validate_variables_in_widget_use(defs, variables, child, is_in_definition));
         }
     } else if let WidgetUse::Loop(widget) = widget {
-        let unknown_var = widget
-            .elements_expr
-            .var_refs_with_span()
-            .iter()
-            .cloned()
-            .map(|(span, var_ref)| (span, var_ref.clone()))
-            .find(|(_, var_ref)| var_ref != &widget.element_name && !variables.contains(var_ref));
-        if let Some((span, var)) = unknown_var {
-            return Err(ValidationError::UnknownVariable { span, name: var, in_definition: is_in_definition });
-        }
+        errors.extend(unknown_variables(&widget.elements_expr, Some(&widget.element_name)));
         let mut variables = variables.clone();
         variables.insert(widget.element_name.clone());
-        validate_variables_in_widget_use(defs, &variables, &widget.body, is_in_definition)?;
+        errors.extend(validate_variables_in_widget_use(defs, &variables, &widget.body, is_in_definition));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{self, from_ast::FromAst};
+
+    #[test]
+    fn test_validate_reports_all_errors() {
+        let defs: HashMap<_, _> =
+            ["(defwidget foo [a] (box (label :text b) (label :text c)))", "(defwidget bar [] (box (foo) (foo :a d)))"]
+                .iter()
+                .map(|code| WidgetDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap())
+                .map(|def| (def.name.clone(), def))
+                .collect();
+        let globals = HashSet::from([VarName::from("c")]);
+
+        let foo_errors = validate_widget_definition(&defs, &globals, &defs["foo"]);
+        assert!(matches!(foo_errors.as_slice(), [ValidationError::UnknownVariable { name, .. }] if name.0 == "b"));
+
+        let bar_errors = validate_widget_definition(&defs, &globals, &defs["bar"]);
+        assert_eq!(bar_errors.len(), 2);
+        assert!(bar_errors.iter().any(|err| matches!(err, ValidationError::MissingAttr { .. })));
+        assert!(bar_errors.iter().any(|err| matches!(err, ValidationError::UnknownVariable { name, .. } if name.0 == "d")));
+    }
+
+    #[test]
+    fn test_validate_unknown_attr() {
+        let defs: HashMap<_, _> = ["(defwidget foo [a ?b] (label :text a))", "(defwidget bar [] (foo :a 1 :b 2 :c 3))"]
+            .iter()
+            .map(|code| WidgetDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap())
+            .map(|def| (def.name.clone(), def))
+            .collect();
+        let errors = validate_widget_definition(&defs, &HashSet::new(), &defs["bar"]);
+        assert!(matches!(
+            errors.as_slice(),
+            [ValidationError::UnknownAttr { widget_name, attr_name, .. }] if widget_name == "foo" && attr_name.0 == "c"
+        ));
+    }
+
+    #[test]
+    fn test_validate_window_definition() {
+        let code = r#"(defwindow bar [screen] :monitor screen :geometry (geometry :width width) (label :text text))"#;
+        let def = WindowDefinition::from_ast(parser::parse_string(0, code).unwrap()).unwrap();
+        let globals = HashSet::from([VarName::from("width"), VarName::from("text")]);
+        let errors = validate_window_definition(&HashMap::new(), &globals, &def);
+        assert!(matches!(errors.as_slice(), [ValidationError::NonArgumentInWindowProperty { name, .. }] if name.0 == "width"));
     }
 
-    Ok(())
+    #[test]
+    fn test_validate_magic_var_definition() {
+        let def =
+            MagicVarDefinition::from_ast(parser::parse_string(0, "(defmagic EWW_CPU :run-while visible)").unwrap()).unwrap();
+        assert!(validate_magic_var_definition(&HashSet::from([VarName::from("visible")]), &def).is_empty());
+        let errors = validate_magic_var_definition(&HashSet::new(), &def);
+        assert!(matches!(errors.as_slice(), [ValidationError::UnknownVariableInMagicVar { name, .. }] if name.0 == "visible"));
+    }
 }
@@ -10,6 +10,8 @@ use eww_shared_util::{Span, VarName};
 pub struct VarDefinition {
     pub name: VarName,
     pub initial_value: DynVal,
+    /// Whether the value should be saved, and restored when the daemon is restarted
+    pub persist: bool,
     pub span: Span,
 }
 
@@ -20,9 +22,11 @@ impl FromAstElementContent for VarDefinition {
         let result: DiagResult<_> = try {
             let (_, name) = iter.expect_symbol()?;
             let (_, initial_value) = iter.expect_literal()?;
+            let mut attrs = iter.expect_key_values()?;
+            let persist = attrs.primitive_optional("persist")?.unwrap_or(false);
             iter.expect_done()?;
-            Self { name: VarName(name), initial_value, span }
+            Self { name: VarName(name), initial_value, persist, span }
         };
-        result.note(r#"Expected format: `(defvar name "initial-value")`"#)
+        result.note(r#"Expected format: `(defvar name "initial-value" [:persist true])`"#)
     }
 }
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 use crate::{
     config::monitor::MonitorIdentifier,
@@ -9,16 +9,29 @@ use crate::{
         from_ast::{FromAst, FromAstElementContent},
     },
 };
-use eww_shared_util::Span;
+use eww_shared_util::{Span, Spanned, VarName};
+use simplexpr::{
+    dynval::{DynVal, FromDynVal},
+    SimplExpr,
+};
 
-use super::{backend_window_options::BackendWindowOptions, widget_use::WidgetUse, window_geometry::WindowGeometry};
+use super::{
+    attributes::AttrError,
+    backend_window_options::BackendWindowOptions,
+    widget_definition::AttrSpec,
+    widget_use::WidgetUse,
+    window_geometry::{WindowGeometry, WindowGeometryDef},
+};
 
 #[derive(Debug, Clone, serde::Serialize, PartialEq)]
 pub struct WindowDefinition {
     pub name: String,
-    pub geometry: Option<WindowGeometry>,
+    /// Arguments that are given when opening the window, which may be used in `monitor`, `geometry` and the widget
+    pub expected_args: Vec<AttrSpec>,
+    pub args_span: Span,
+    pub geometry: Option<WindowGeometryDef>,
     pub stacking: WindowStacking,
-    pub monitor: Option<MonitorIdentifier>,
+    pub monitor: Option<SimplExpr>,
     pub widget: WidgetUse,
     pub resizable: bool,
     pub backend_options: BackendWindowOptions,
@@ -28,17 +41,49 @@ impl FromAstElementContent for WindowDefinition {
     const ELEMENT_NAME: &'static str = "defwindow";
 
     fn from_tail<I: Iterator<Item = Ast>>(_span: Span, mut iter: AstIterator<I>) -> DiagResult<Self> {
-        let (_, name) = iter.expect_symbol()?;
+        let (name_span, name) = iter.expect_symbol()?;
+        // the argument list is optional, as most windows don't need any arguments
+        let (args_span, expected_args) = match iter.expect_array() {
+            Ok((args_span, expected_args)) => {
+                (args_span, expected_args.into_iter().map(AttrSpec::from_ast).collect::<DiagResult<_>>()?)
+            }
+            Err(_) => (name_span.point_span_at_end(), Vec::new()),
+        };
         let mut attrs = iter.expect_key_values()?;
-        let monitor = attrs.primitive_optional("monitor")?;
+        let monitor = attrs.ast_optional("monitor")?;
         let resizable = attrs.primitive_optional("resizable")?.unwrap_or(true);
         let stacking = attrs.primitive_optional("stacking")?.unwrap_or(WindowStacking::Foreground);
         let geometry = attrs.ast_optional("geometry")?;
         let backend_options = BackendWindowOptions::from_attrs(&mut attrs)?;
         let widget = iter.expect_any().map_err(DiagError::from).and_then(WidgetUse::from_ast)?;
         iter.expect_done()?;
-        Ok(Self { name, monitor, resizable, widget, stacking, geometry, backend_options })
+        Ok(Self { name, expected_args, args_span, monitor, resizable, widget, stacking, geometry, backend_options })
+    }
+}
+
+impl WindowDefinition {
+    /// Evaluate the `monitor` of this window, given the values of the window arguments.
+    pub fn eval_monitor(&self, local_variables: &HashMap<VarName, DynVal>) -> DiagResult<Option<MonitorIdentifier>> {
+        self.monitor.as_ref().map(|expr| eval_window_attr(expr, local_variables)).transpose()
     }
+
+    /// Evaluate the `geometry` of this window, given the values of the window arguments.
+    pub fn eval_geometry(&self, local_variables: &HashMap<VarName, DynVal>) -> DiagResult<Option<WindowGeometry>> {
+        self.geometry.as_ref().map(|geometry| geometry.eval(local_variables)).transpose()
+    }
+}
+
+/// Evaluate an attribute of a window definition, which may only reference the arguments of the window.
+pub(super) fn eval_window_attr<T, E>(expr: &SimplExpr, local_variables: &HashMap<VarName, DynVal>) -> DiagResult<T>
+where
+    E: std::error::Error + 'static + Sync + Send,
+    T: FromDynVal<Err = E>,
+{
+    Ok(expr
+        .eval(local_variables)
+        .map_err(|err| AttrError::EvaluationError(expr.span(), err))?
+        .read_as()
+        .map_err(|e| AttrError::Other(expr.span(), Box::new(e)))?)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::{
     enum_parse,
     error::DiagResult,
@@ -6,9 +8,13 @@ use crate::{
     value::Coords,
 };
 
-use super::window_definition::EnumParseError;
-use eww_shared_util::Span;
+use super::window_definition::{eval_window_attr, EnumParseError};
+use eww_shared_util::{Span, VarName};
 use serde::{Deserialize, Serialize};
+use simplexpr::{
+    dynval::{DynVal, FromDynVal},
+    SimplExpr,
+};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, smart_default::SmartDefault, Serialize, Deserialize, strum::Display)]
 pub enum AnchorAlignment {
@@ -109,25 +115,70 @@ pub struct WindowGeometry {
     pub size: Coords,
 }
 
-impl FromAstElementContent for WindowGeometry {
+/// The geometry of a window as given in its definition, which may reference the arguments of the window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowGeometryDef {
+    pub anchor_point: Option<SimplExpr>,
+    pub x: Option<SimplExpr>,
+    pub y: Option<SimplExpr>,
+    pub width: Option<SimplExpr>,
+    pub height: Option<SimplExpr>,
+}
+
+impl FromAstElementContent for WindowGeometryDef {
     const ELEMENT_NAME: &'static str = "geometry";
 
     fn from_tail<I: Iterator<Item = Ast>>(_span: Span, mut iter: AstIterator<I>) -> DiagResult<Self> {
         let mut attrs = iter.expect_key_values()?;
         iter.expect_done()
             .map_err(|e| e.to_diagnostic().with_notes(vec!["Check if you are missing a colon in front of a key".to_string()]))?;
+        let geometry = WindowGeometryDef {
+            anchor_point: attrs.ast_optional("anchor")?,
+            x: attrs.ast_optional("x")?,
+            y: attrs.ast_optional("y")?,
+            width: attrs.ast_optional("width")?,
+            height: attrs.ast_optional("height")?,
+        };
+        // geometries that don't depend on any window arguments can already be checked here
+        if geometry.var_refs().is_empty() {
+            geometry.eval(&HashMap::new())?;
+        }
+        Ok(geometry)
+    }
+}
+
+impl WindowGeometryDef {
+    /// Evaluate the geometry, given the values of the window arguments.
+    pub fn eval(&self, local_variables: &HashMap<VarName, DynVal>) -> DiagResult<WindowGeometry> {
         Ok(WindowGeometry {
-            anchor_point: attrs.primitive_optional("anchor")?.unwrap_or_default(),
+            anchor_point: eval_optional(&self.anchor_point, local_variables)?.unwrap_or_default(),
             size: Coords {
-                x: attrs.primitive_optional("width")?.unwrap_or_default(),
-                y: attrs.primitive_optional("height")?.unwrap_or_default(),
+                x: eval_optional(&self.width, local_variables)?.unwrap_or_default(),
+                y: eval_optional(&self.height, local_variables)?.unwrap_or_default(),
             },
             offset: Coords {
-                x: attrs.primitive_optional("x")?.unwrap_or_default(),
-                y: attrs.primitive_optional("y")?.unwrap_or_default(),
+                x: eval_optional(&self.x, local_variables)?.unwrap_or_default(),
+                y: eval_optional(&self.y, local_variables)?.unwrap_or_default(),
             },
         })
     }
+
+    /// All variables referenced in any of the attributes of the geometry.
+    pub fn var_refs(&self) -> Vec<(Span, &VarName)> {
+        [&self.anchor_point, &self.x, &self.y, &self.width, &self.height]
+            .into_iter()
+            .flatten()
+            .flat_map(|expr| expr.var_refs_with_span())
+            .collect()
+    }
+}
+
+fn eval_optional<T, E>(expr: &Option<SimplExpr>, local_variables: &HashMap<VarName, DynVal>) -> DiagResult<Option<T>>
+where
+    E: std::error::Error + 'static + Sync + Send,
+    T: FromDynVal<Err = E>,
+{
+    expr.as_ref().map(|expr| eval_window_attr(expr, local_variables)).transpose()
 }
 
 impl WindowGeometry {
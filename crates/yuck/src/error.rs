@@ -4,6 +4,7 @@ use crate::{
 };
 use codespan_reporting::diagnostic;
 use eww_shared_util::{Span, Spanned};
+use itertools::Itertools;
 use simplexpr::dynval;
 use thiserror::Error;
 
@@ -14,6 +15,7 @@ pub type DiagResult<T> = Result<T, DiagError>;
 pub struct DiagError(pub diagnostic::Diagnostic<usize>);
 
 static_assertions::assert_impl_all!(DiagError: Send, Sync);
+static_assertions::assert_impl_all!(DiagErrors: Send, Sync);
 static_assertions::assert_impl_all!(dynval::ConversionError: Send, Sync);
 static_assertions::assert_impl_all!(lalrpop_util::ParseError < usize, lexer::Token, parse_error::ParseError>: Send, Sync);
 
@@ -36,6 +38,29 @@ impl DiagError {
     }
 }
 
+/// Multiple errors that are reported together.
+/// Returned by steps that continue after recoverable errors, like loading and validating the configuration.
+#[derive(Debug, Error)]
+#[error("{}", .0.iter().map(|err| err.0.to_message()).join("\n"))]
+pub struct DiagErrors(pub Vec<DiagError>);
+
+impl DiagErrors {
+    /// Succeed if there are no errors, and fail with all of them otherwise.
+    pub fn from_errors(errors: Vec<DiagError>) -> Result<(), DiagErrors> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(DiagErrors(errors))
+        }
+    }
+}
+
+impl From<DiagError> for DiagErrors {
+    fn from(err: DiagError) -> Self {
+        DiagErrors(vec![err])
+    }
+}
+
 pub fn get_parse_error_span<T, E: Spanned>(file_id: usize, err: &lalrpop_util::ParseError<usize, T, E>) -> Span {
     use lalrpop_util::ParseError::*;
     match err {
@@ -122,6 +122,12 @@ impl ToDiagnostic for ValidationError {
                 }
                 diag
             }
+            ValidationError::UnknownAttr { widget_name, attr_name, arg_list_span, attr_span } => gen_diagnostic! {
+                msg = self,
+                label = attr_span => "Given here",
+                note = format!("Hint: Either remove it, or add `{}` to the argument-list of `{}`", attr_name, widget_name)
+            }
+            .with_label(span_to_secondary_label(*arg_list_span).with_message("Arguments are defined here")),
             ValidationError::UnknownVariable { span, name, in_definition } => {
                 let diag = gen_diagnostic! {
                     msg = self,
@@ -129,7 +135,8 @@ impl ToDiagnostic for ValidationError {
                     note = if *in_definition {
                         "Hint: Either define it as a global variable, or add it to the argument-list of your `defwidget` and pass it as an argument"
                     } else {
-                        "Hint: Define it as a global variable"
+                        "Hint: Either define it as a global variable, or add it to the argument-list of your `defwindow` and pass it \
+                         when opening the window"
                     }
                 };
 
@@ -142,6 +149,24 @@ impl ToDiagnostic for ValidationError {
 
                 diag.with_notes(extra_notes)
             }
+            ValidationError::NonArgumentInWindowProperty { span, name } => gen_diagnostic! {
+                msg = self,
+                label = span => "Used here",
+                note = format!(
+                    "Hint: These are evaluated when opening the window, so global variables can't be used here. Add `{}` to the \
+                     argument-list of your `defwindow` and pass it when opening the window",
+                    name
+                )
+            },
+            ValidationError::UnknownVariableInMagicVar { span, name } => gen_diagnostic! {
+                msg = self,
+                label = span => "Used here",
+                note = format!(
+                    "Hint: The `run-while` condition of a `defmagic` can only use global variables, so define `{}` using `defvar`, \
+                     `defpoll` or `deflisten`",
+                    name
+                )
+            },
             ValidationError::AccidentalBuiltinOverride(span, _widget_name) => gen_diagnostic! {
                 msg = self,
                 label = span => "Defined here",